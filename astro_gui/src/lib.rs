@@ -1,7 +1,16 @@
 use astro_math::*;
-use std::collections::{HashMap, HashSet};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display, Formatter},
+    hash::Hash,
+    iter::FromIterator,
+};
 
-#[derive(Clone, Copy, Debug)]
+pub mod software;
+mod tests;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -10,47 +19,497 @@ pub struct Color {
 }
 
 impl Color {
-    pub const BLACK: Color = Self::from_packed(0x000000_FF);
-    pub const MAGENTA: Color = Self::from_packed(0xFF00FF_FF);
-    pub const WHITE: Color = Self::from_packed(0xFFFFFF_FF);
+    pub const BLACK: Color = Self::from_packed(0x000000FF);
+    pub const MAGENTA: Color = Self::from_packed(0xFF00FFFF);
+    pub const RED: Color = Self::from_packed(0xFF0000FF);
+    pub const WHITE: Color = Self::from_packed(0xFFFFFFFF);
 
     pub const fn from_packed(packed: u32) -> Self {
         Self {
             r: (packed >> 24) as _,
             g: ((packed >> 16) & 0xFF) as _,
             b: ((packed >> 8) & 0xFF) as _,
-            a: ((packed >> 0) & 0xFF) as _,
+            a: (packed & 0xFF) as _,
         }
     }
+
+    /// Returns a copy of this color with a different opacity.
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Like `with_alpha`, but takes an opacity from 0 to 1.
+    pub fn with_alpha_f(self, a: f32) -> Self {
+        self.with_alpha((a.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
 }
 
-#[derive(Clone, Debug)]
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+/// Formats the color as `#rrggbbaa`.
+impl Display for Color {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.r, self.g, self.b, self.a
+        )
+    }
+}
+
+/// Same as `Display`, to keep dumps of command buffers readable.
+impl Debug for Color {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum FillMode {
+    /// Nothing is filled. Commands are still recorded, so that backends can e.g. stroke them or
+    /// use them for hit testing.
+    None,
     Solid(Color),
 }
 
-#[derive(Clone, Debug)]
-pub enum RenderCommand {
-    Clear(FillMode),
+/// How an image is fit into a rectangle of a different size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFit {
+    /// Scales the image uniformly so that all of it is visible, leaving empty space on two sides
+    /// if the aspect ratios don't match.
+    Contain,
+    /// Scales the image uniformly so that it covers the whole rectangle, cropping two of its sides
+    /// if the aspect ratios don't match.
+    Cover,
+    /// Keeps the image at its natural size, centered in the rectangle and cropped if it is larger.
+    Fill,
+    /// Scales the image non-uniformly so that it exactly matches the rectangle.
+    Stretch,
+}
+
+impl ImageFit {
+    /// Returns which part of an image of the specified size should be drawn (in pixels of the
+    /// image) and where it should be drawn to, which is always inside `dest`.
+    pub fn compute_rects(&self, image_size: Size, dest: Rect) -> (Rect, Rect) {
+        let full_image = Rect::new(0, image_size);
+        let centered = |size: Size| Rect::new(dest.top_left + (dest.size - size) / 2.0, size);
+        match self {
+            Self::Contain => {
+                let scale = (dest.size.x / image_size.x).min(dest.size.y / image_size.y);
+                (full_image, centered(image_size * scale))
+            }
+            Self::Cover => {
+                let scale = (dest.size.x / image_size.x).max(dest.size.y / image_size.y);
+                let visible = dest.size / scale;
+                let source = Rect::new((image_size - visible) / 2.0, visible);
+                (source, dest)
+            }
+            Self::Fill => {
+                let visible =
+                    Size::new(image_size.x.min(dest.size.x), image_size.y.min(dest.size.y));
+                let source = Rect::new((image_size - visible) / 2.0, visible);
+                (source, centered(visible))
+            }
+            Self::Stretch => (full_image, dest),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    /// Alternating lengths of drawn and skipped segments. `None` means the stroke is solid.
+    pub dash: Option<Vec<f32>>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: f32,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            dash: None,
+            dash_offset: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    MoveTo(Point),
+    LineTo(Point),
+    /// A quadratic bezier curve with one control point followed by the end point.
+    QuadTo(Point, Point),
+    /// A cubic bezier curve with two control points followed by the end point.
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// Something for a backend to draw. `F` is how fills are described, which is normally `FillMode`
+/// but can be something else after post-processing, see `dedup_fills`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenderCommand<F = FillMode> {
+    Clear(F),
     DrawRect {
         transform: Transform,
         top_left: Point,
         size: Size,
-        fill: FillMode,
+        fill: F,
+    },
+    /// Outlines a rectangle, painting the outline with `fill`.
+    StrokeRect {
+        transform: Transform,
+        top_left: Point,
+        size: Size,
+        stroke: StrokeStyle,
+        fill: F,
+    },
+    /// A series of connected line segments. If `closed` is true and a fill is present, the
+    /// enclosed polygon is filled with it. Otherwise, the segments are stroked with the fill.
+    DrawPath {
+        transform: Transform,
+        points: Vec<Point>,
+        closed: bool,
+        stroke: StrokeStyle,
+        fill: Option<F>,
+    },
+    /// Like `DrawPath`, but made of segments which can be curved.
+    DrawBezierPath {
+        transform: Transform,
+        segments: Vec<PathSegment>,
+        stroke: StrokeStyle,
+        fill: Option<F>,
+    },
+    /// A blurred shadow cast by a rectangle. Blurring is left up to the backend.
+    DrawShadow {
+        transform: Transform,
+        top_left: Point,
+        size: Size,
+        blur: f32,
+        offset: Vec2,
+        color: Color,
+    },
+    /// Restricts the following commands in the same layer to the inside of a rectangle, until the
+    /// matching `PopClip`. Clips nest, with the visible area being the intersection of all of them.
+    PushClip {
+        transform: Transform,
+        top_left: Point,
+        size: Size,
     },
+    /// Undoes the most recent `PushClip`.
+    PopClip,
 }
 
-#[derive(Default, Debug)]
-pub struct Layer {
-    command_buffer: Vec<RenderCommand>,
+impl<F> RenderCommand<F> {
+    /// The name of this command's variant, e.g. `"DrawRect"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Clear(..) => "Clear",
+            Self::DrawRect { .. } => "DrawRect",
+            Self::StrokeRect { .. } => "StrokeRect",
+            Self::DrawPath { .. } => "DrawPath",
+            Self::DrawBezierPath { .. } => "DrawBezierPath",
+            Self::DrawShadow { .. } => "DrawShadow",
+            Self::PushClip { .. } => "PushClip",
+            Self::PopClip => "PopClip",
+        }
+    }
+
+    /// Returns the transform the command is drawn with, if it has one.
+    pub fn transform_mut(&mut self) -> Option<&mut Transform> {
+        match self {
+            Self::Clear(..) | Self::PopClip => None,
+            Self::DrawRect { transform, .. }
+            | Self::StrokeRect { transform, .. }
+            | Self::DrawPath { transform, .. }
+            | Self::DrawBezierPath { transform, .. }
+            | Self::DrawShadow { transform, .. }
+            | Self::PushClip { transform, .. } => Some(transform),
+        }
+    }
+
+    /// True if `point`, in the same space as the command's transform maps to, is inside the shape
+    /// this command draws. Only rects are supported, this is always false for other commands.
+    pub fn contains_point(&self, point: impl Into<Point>) -> bool {
+        match self {
+            Self::DrawRect {
+                transform,
+                top_left,
+                size,
+                ..
+            }
+            | Self::StrokeRect {
+                transform,
+                top_left,
+                size,
+                ..
+            } => match transform.inverse() {
+                Some(inverse) => Rect::new(*top_left, *size).contains(point.into() * inverse),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Converts the fills of this command to a different type.
+    pub fn map_fill<G>(self, mut f: impl FnMut(F) -> G) -> RenderCommand<G> {
+        match self {
+            Self::Clear(fill) => RenderCommand::Clear(f(fill)),
+            Self::DrawRect {
+                transform,
+                top_left,
+                size,
+                fill,
+            } => RenderCommand::DrawRect {
+                transform,
+                top_left,
+                size,
+                fill: f(fill),
+            },
+            Self::StrokeRect {
+                transform,
+                top_left,
+                size,
+                stroke,
+                fill,
+            } => RenderCommand::StrokeRect {
+                transform,
+                top_left,
+                size,
+                stroke,
+                fill: f(fill),
+            },
+            Self::DrawPath {
+                transform,
+                points,
+                closed,
+                stroke,
+                fill,
+            } => RenderCommand::DrawPath {
+                transform,
+                points,
+                closed,
+                stroke,
+                fill: fill.map(f),
+            },
+            Self::DrawBezierPath {
+                transform,
+                segments,
+                stroke,
+                fill,
+            } => RenderCommand::DrawBezierPath {
+                transform,
+                segments,
+                stroke,
+                fill: fill.map(f),
+            },
+            Self::DrawShadow {
+                transform,
+                top_left,
+                size,
+                blur,
+                offset,
+                color,
+            } => RenderCommand::DrawShadow {
+                transform,
+                top_left,
+                size,
+                blur,
+                offset,
+                color,
+            },
+            Self::PushClip {
+                transform,
+                top_left,
+                size,
+            } => RenderCommand::PushClip {
+                transform,
+                top_left,
+                size,
+            },
+            Self::PopClip => RenderCommand::PopClip,
+        }
+    }
 }
 
-impl Layer {
-    pub fn borrow_commands(&self) -> &[RenderCommand] {
+/// A filled rectangle which can be drawn without any transformation, see
+/// `Layer::split_axis_aligned`.
+#[derive(Clone, Debug)]
+pub struct AxisAlignedRect {
+    pub top_left: Point,
+    pub size: Size,
+    pub fill: FillMode,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer<F = FillMode> {
+    command_buffer: Vec<RenderCommand<F>>,
+}
+
+impl<F> Default for Layer<F> {
+    fn default() -> Self {
+        Self {
+            command_buffer: Vec::new(),
+        }
+    }
+}
+
+impl<F> Layer<F> {
+    pub fn borrow_commands(&self) -> &[RenderCommand<F>] {
         &self.command_buffer[..]
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.command_buffer.is_empty()
+    }
+
+    /// Returns a copy of this layer where everything is moved by the specified offset.
+    pub fn translated(&self, offset: impl Into<Vec2>) -> Self
+    where
+        F: Clone,
+    {
+        let offset = offset.into();
+        let mut result = self.clone();
+        for command in &mut result.command_buffer {
+            if let Some(transform) = command.transform_mut() {
+                *transform = transform.translated(offset);
+            }
+        }
+        result
+    }
+
+    /// Removes every `Clear` which is immediately followed by another `Clear`, since the second
+    /// one overwrites everything the first one did.
+    fn drop_redundant_clears(&mut self) {
+        // dedup_by removes the later of two matching commands, so swap them first to keep the
+        // later one instead.
+        self.command_buffer.dedup_by(|next, previous| {
+            let redundant = matches!(
+                (&*previous, &*next),
+                (RenderCommand::Clear(..), RenderCommand::Clear(..))
+            );
+            if redundant {
+                std::mem::swap(previous, next);
+            }
+            redundant
+        });
+    }
+}
+
+impl Layer {
+    /// Separates the rects in this layer which are only translated, and so can be drawn as simple
+    /// axis-aligned blits, from the commands which need their full transform applied. Note that
+    /// this loses the relative order of commands in the two lists.
+    pub fn split_axis_aligned(&self) -> (Vec<AxisAlignedRect>, Vec<RenderCommand>) {
+        let mut axis_aligned = Vec::new();
+        let mut others = Vec::new();
+        for command in &self.command_buffer {
+            match command {
+                RenderCommand::DrawRect {
+                    transform,
+                    top_left,
+                    size,
+                    fill,
+                } if transform.is_translation() => axis_aligned.push(AxisAlignedRect {
+                    top_left: *top_left + (transform.ix, transform.iy),
+                    size: *size,
+                    fill: fill.clone(),
+                }),
+                _ => others.push(command.clone()),
+            }
+        }
+        (axis_aligned, others)
+    }
+}
+
+/// Replaces the fills of all the commands in the provided layers with indexes into a palette of
+/// distinct fills, which is returned alongside the new layers. This saves memory in large scenes
+/// and lets backends group commands by fill.
+pub fn dedup_fills(layers: Vec<Layer>) -> (Vec<FillMode>, Vec<Layer<usize>>) {
+    let mut palette = Vec::new();
+    let mut indexes = HashMap::new();
+    let mut intern = |fill: FillMode| {
+        *indexes.entry(fill).or_insert_with_key(|fill| {
+            palette.push(fill.clone());
+            palette.len() - 1
+        })
+    };
+    let layers = layers
+        .into_iter()
+        .map(|layer| Layer {
+            command_buffer: layer
+                .command_buffer
+                .into_iter()
+                .map(|command| command.map_fill(&mut intern))
+                .collect(),
+        })
+        .collect();
+    (palette, layers)
+}
+
+/// Returns a copy of the provided layers where everything is moved by the specified offset.
+pub fn translate_layers(layers: &[Layer], offset: impl Into<Vec2>) -> Vec<Layer> {
+    let offset = offset.into();
+    layers
+        .iter()
+        .map(|layer| layer.translated(offset))
+        .collect()
+}
+
+/// True if both lists of layers contain exactly the same commands. Backends can use this to skip
+/// redrawing a frame which is identical to the previous one.
+pub fn layers_eq(a: &[Layer], b: &[Layer]) -> bool {
+    a == b
+}
+
+/// Combines two rendered layer lists so that everything in `overlay` is drawn on top of
+/// everything in `base`. Useful for reusing the cached output of a separately rendered sub-tree.
+pub fn compose_layers(base: Vec<Layer>, overlay: Vec<Layer>) -> Vec<Layer> {
+    let mut result = base;
+    result.extend(overlay);
+    result
+}
+
+/// Command buffers left over from previous frames, which can be reused to avoid allocating new
+/// ones. See `GuiDrawer::draw_with_buffers`.
+#[derive(Default)]
+pub struct BufferPool {
+    buffers: Vec<Vec<RenderCommand>>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Keeps the command buffers of the provided layers so that they can be reused.
+    pub fn recycle(&mut self, layers: impl IntoIterator<Item = Layer>) {
+        for layer in layers {
+            let mut buffer = layer.command_buffer;
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// How many buffers are available for reuse.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+
+    fn take(&mut self) -> Vec<RenderCommand> {
+        self.buffers.pop().unwrap_or_default()
+    }
 }
 
+#[derive(Clone)]
 struct LayerGroup {
     layers: HashMap<i8, Layer>,
     subgroups: HashMap<i8, Vec<LayerGroup>>,
@@ -64,15 +523,21 @@ impl LayerGroup {
         }
     }
 
-    fn borrow_layer_mut(&mut self, height: i8) -> &mut Layer {
-        // We call this twice because of mutable borrow rules, hopefully it is easily
-        // optimized away.
-        if self.layers.get_mut(&height).is_some() {
-            self.layers.get_mut(&height).unwrap()
-        } else {
-            self.layers.insert(height, Default::default());
-            self.layers.get_mut(&height).unwrap()
+    /// Creates a group which flattens to the provided layers, in the same order.
+    fn from_layers(layers: Vec<Layer>) -> Self {
+        let mut result = Self::new();
+        for layer in layers {
+            let mut subgroup = Self::new();
+            subgroup.layers.insert(0, layer);
+            result.add_subgroup(0, subgroup);
         }
+        result
+    }
+
+    fn borrow_layer_mut(&mut self, height: i8, pool: &mut BufferPool) -> &mut Layer {
+        self.layers.entry(height).or_insert_with(|| Layer {
+            command_buffer: pool.take(),
+        })
     }
 
     fn add_subgroup(&mut self, height: i8, subgroup: LayerGroup) {
@@ -83,12 +548,68 @@ impl LayerGroup {
         }
     }
 
+    /// Calls `f` on the transform of every command in this group and its subgroups.
+    fn map_transforms(&mut self, f: &impl Fn(Transform) -> Transform) {
+        for layer in self.layers.values_mut() {
+            for command in &mut layer.command_buffer {
+                if let Some(transform) = command.transform_mut() {
+                    *transform = f(*transform);
+                }
+            }
+        }
+        for subgroup in self.subgroups.values_mut().flatten() {
+            subgroup.map_transforms(f);
+        }
+    }
+
+    /// Records how many commands each layer has and how many subgroups are at each height.
+    fn sizes(&self) -> GroupSizes {
+        GroupSizes {
+            layers: self
+                .layers
+                .iter()
+                .map(|(&height, layer)| (height, layer.command_buffer.len()))
+                .collect(),
+            subgroups: self
+                .subgroups
+                .iter()
+                .map(|(&height, list)| (height, list.len()))
+                .collect(),
+        }
+    }
+
+    /// Discards everything added since `sizes` was recorded.
+    fn truncate(&mut self, sizes: &GroupSizes, pool: &mut BufferPool) {
+        let new_layers: Vec<i8> = (self.layers.keys())
+            .filter(|height| !sizes.layers.contains_key(height))
+            .copied()
+            .collect();
+        pool.recycle(
+            new_layers
+                .iter()
+                .map(|height| self.layers.remove(height).unwrap()),
+        );
+        for (height, layer) in &mut self.layers {
+            layer.command_buffer.truncate(sizes.layers[height]);
+        }
+        self.subgroups
+            .retain(|height, _| sizes.subgroups.contains_key(height));
+        for (height, list) in &mut self.subgroups {
+            list.truncate(sizes.subgroups[height]);
+        }
+    }
+
     fn flatten(self) -> Vec<Layer> {
         let mut result = Vec::new();
         self.flatten_into(&mut result);
         result
     }
 
+    /// Appends the layers of this group to `target`, bottom to top. Heights are visited in
+    /// increasing order. At each height, the group's own layer comes first, followed by the
+    /// subgroups at that height in the order they were added, each flattened recursively. A
+    /// subgroup is therefore always entirely above everything at lower heights and entirely below
+    /// everything at higher heights, no matter what heights are used inside it.
     fn flatten_into(mut self, target: &mut Vec<Layer>) {
         let mut all_layer_indexes = HashSet::new();
         for &key in self.layers.keys() {
@@ -100,7 +621,13 @@ impl LayerGroup {
         let mut sorted_layer_indexes: Vec<_> = all_layer_indexes.into_iter().collect();
         sorted_layer_indexes.sort();
         for index in sorted_layer_indexes {
-            self.layers.remove(&index).map(|layer| target.push(layer));
+            if let Some(mut layer) = self.layers.remove(&index) {
+                layer.drop_redundant_clears();
+                // Backends shouldn't have to iterate over layers that do nothing.
+                if !layer.is_empty() {
+                    target.push(layer);
+                }
+            }
             if let Some(subgroups) = self.subgroups.remove(&index) {
                 for subgroup in subgroups {
                     subgroup.flatten_into(target);
@@ -110,10 +637,27 @@ impl LayerGroup {
     }
 }
 
+/// See `LayerGroup::sizes`.
+struct GroupSizes {
+    layers: HashMap<i8, usize>,
+    subgroups: HashMap<i8, usize>,
+}
+
+/// A point in drawing which a `DrawContext` can be rolled back to, see `DrawContext::checkpoint`.
+pub struct Checkpoint {
+    group_stack_size: usize,
+    state_stack_size: usize,
+    state: DrawContextState,
+    path_len: usize,
+    group_sizes: GroupSizes,
+    num_overlays: usize,
+}
+
 #[derive(Clone)]
 struct DrawContextState {
     transform: Transform,
     fill_mode: FillMode,
+    stroke_style: StrokeStyle,
     layer: i8,
 }
 
@@ -122,23 +666,82 @@ impl DrawContextState {
         Self {
             transform: Transform::identity(),
             fill_mode: FillMode::Solid(Color::WHITE),
+            stroke_style: Default::default(),
             layer: 0,
         }
     }
 }
 
+/// Height of a layer or layer group, see `DrawContext::set_layer`. Higher layers are drawn on top
+/// of lower ones. The constants give widgets which don't know about each other an agreed upon
+/// height for common kinds of content. Plain `i8` heights convert into this as well.
+///
+/// Widgets may only use heights from `MIN_APP` to `MAX_APP`. The heights outside that range are
+/// reserved for astro_gui itself, e.g. for `DrawContext::draw_overlay`. This is checked in debug
+/// builds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LayerId(pub i8);
+
+impl LayerId {
+    pub const BACKGROUND: LayerId = LayerId(-100);
+    pub const CONTENT: LayerId = LayerId(0);
+    pub const OVERLAY: LayerId = LayerId(50);
+    pub const MODAL: LayerId = LayerId(100);
+
+    pub const MIN_APP: LayerId = LayerId(-120);
+    pub const MAX_APP: LayerId = LayerId(120);
+
+    /// True if this height is reserved for astro_gui, see the type documentation.
+    pub fn is_reserved(self) -> bool {
+        self < Self::MIN_APP || self > Self::MAX_APP
+    }
+
+    fn debug_assert_not_reserved(self) {
+        debug_assert!(
+            !self.is_reserved(),
+            "Layer height {} is reserved for astro_gui, widgets must use heights from {} to {}",
+            self.0,
+            Self::MIN_APP.0,
+            Self::MAX_APP.0
+        );
+    }
+}
+
+impl From<i8> for LayerId {
+    fn from(height: i8) -> Self {
+        Self(height)
+    }
+}
+
+/// Height of the layer group that `DrawContext::draw_overlay` draws into. Reserved, so that no
+/// widget can draw above overlays.
+const OVERLAY_HEIGHT: i8 = i8::MAX;
+
 pub struct DrawContext {
     layer_group_stack: Vec<(i8, LayerGroup)>,
     state_stack: Vec<DrawContextState>,
     state: DrawContextState,
+    /// Segments of the path currently being built with the `path_*` methods.
+    path: Vec<PathSegment>,
+    /// Where the command buffers of new layers come from.
+    pool: BufferPool,
+    /// If true, `draw_child` outlines the bounds of every child it draws.
+    pub debug_outlines: bool,
 }
 
 impl DrawContext {
     fn new() -> Self {
+        Self::with_buffers(BufferPool::new())
+    }
+
+    fn with_buffers(pool: BufferPool) -> Self {
         Self {
             layer_group_stack: vec![(0, LayerGroup::new())],
             state_stack: Vec::new(),
             state: DrawContextState::new(),
+            path: Vec::new(),
+            pool,
+            debug_outlines: false,
         }
     }
 
@@ -151,8 +754,50 @@ impl DrawContext {
     }
 
     pub fn pop_state(&mut self) {
-        debug_assert!(self.state_stack.len() > 0);
-        self.state = self.state_stack.pop().unwrap();
+        self.state = self
+            .state_stack
+            .pop()
+            .expect("pop_state() called without a matching push_state()");
+    }
+
+    /// Remembers the current state and contents so that anything drawn afterwards can be discarded
+    /// with `rollback`, e.g. to try out drawing something and throw it away if it doesn't fit.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            group_stack_size: self.layer_group_stack.len(),
+            state_stack_size: self.state_stack.len(),
+            state: self.state.clone(),
+            path_len: self.path.len(),
+            group_sizes: self.layer_group_stack.last().unwrap().1.sizes(),
+            num_overlays: self.num_overlays(),
+        }
+    }
+
+    /// Discards everything drawn since `checkpoint` was created and restores the state from that
+    /// time. Every layer group begun and every state pushed since then must have been ended or
+    /// popped again.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        assert!(
+            self.layer_group_stack.len() == checkpoint.group_stack_size
+                && self.state_stack.len() == checkpoint.state_stack_size,
+            "rollback() called with unbalanced push_state() or begin_layer_group() calls since the checkpoint"
+        );
+        self.state = checkpoint.state;
+        self.path.truncate(checkpoint.path_len);
+        let top = &mut self.layer_group_stack.last_mut().unwrap().1;
+        top.truncate(&checkpoint.group_sizes, &mut self.pool);
+        if let Some(overlays) = self.layer_group_stack[0]
+            .1
+            .subgroups
+            .get_mut(&OVERLAY_HEIGHT)
+        {
+            overlays.truncate(checkpoint.num_overlays);
+        }
+    }
+
+    fn num_overlays(&self) -> usize {
+        let root = &self.layer_group_stack[0].1;
+        root.subgroups.get(&OVERLAY_HEIGHT).map_or(0, Vec::len)
     }
 
     pub fn set_transform(&mut self, new: Transform) {
@@ -160,14 +805,60 @@ impl DrawContext {
     }
 
     pub fn translate(&mut self, offset: impl Into<Point>) {
-        self.state.transform = self.state.transform.translated(offset.into());
+        self.apply_transform(Transform::translate(offset.into()));
+    }
+
+    pub fn scale(&mut self, amount: impl Into<Size>) {
+        self.apply_transform(Transform::scale(amount.into()));
+    }
+
+    /// Makes everything drawn afterwards be transformed by `local` before the current transform.
+    pub fn apply_transform(&mut self, local: Transform) {
+        self.state.transform = self.to_current_space(local);
+    }
+
+    /// Converts a transform which is relative to the current one, i.e. one that would be produced
+    /// by starting from the identity, into an absolute transform. Points are row vectors, so the
+    /// local transform has to come first.
+    fn to_current_space(&self, local: Transform) -> Transform {
+        local * self.state.transform
+    }
+
+    /// Draws a group recorded by a different context as if its commands had been drawn here.
+    /// The whole group is placed above anything else on the current layer.
+    fn draw_recorded(&mut self, mut group: LayerGroup) {
+        group.map_transforms(&|transform| self.to_current_space(transform));
+        let layer = self.state.layer;
+        self.top_layer_group().add_subgroup(layer, group);
+    }
+
+    /// Draws layers produced by a separate draw, such as an independently laid out sub-UI, as if
+    /// their commands had been drawn here. They keep their order and are placed above anything
+    /// else on the current layer.
+    pub fn splice_layers(&mut self, layers: Vec<Layer>) {
+        self.draw_recorded(LayerGroup::from_layers(layers));
     }
 
+    /// Draws a child which was laid out to have the specified size at the specified offset.
     pub fn draw_child<C: GuiConfig>(
         &mut self,
         child: &impl RenderWidget<C>,
         offset: impl Into<Point>,
+        size: impl Into<Size>,
     ) {
+        let offset = offset.into();
+        self.draw_child_impl(child, offset);
+        if self.debug_outlines {
+            self.push_state();
+            self.translate(offset);
+            self.fill_solid_color(Color::MAGENTA);
+            self.set_stroke_style(Default::default());
+            self.stroke_rect(0, size);
+            self.pop_state();
+        }
+    }
+
+    fn draw_child_impl<C: GuiConfig>(&mut self, child: &impl RenderWidget<C>, offset: Point) {
         let old_stack_size = self.get_state_stack_size();
         let old_layer_stack_size = self.get_layer_group_stack_size();
 
@@ -180,6 +871,22 @@ impl DrawContext {
         debug_assert_eq!(old_layer_stack_size, self.get_layer_group_stack_size());
     }
 
+    /// Draws a child into a layer group which is composited above all other content, regardless of
+    /// where in the widget tree this is called from. Useful for tooltips, dropdowns, etc.
+    pub fn draw_overlay<C: GuiConfig>(
+        &mut self,
+        child: &impl RenderWidget<C>,
+        offset: impl Into<Point>,
+    ) {
+        self.begin_layer_group_impl(OVERLAY_HEIGHT);
+        self.draw_child_impl(child, offset.into());
+        let (height, group) = self.layer_group_stack.pop().unwrap();
+        // Attach to the root group instead of the current group so that the overlay is above
+        // everything, not just above its siblings.
+        self.layer_group_stack[0].1.add_subgroup(height, group);
+        self.pop_state();
+    }
+
     pub fn set_fill_mode(&mut self, new: FillMode) {
         self.state.fill_mode = new;
     }
@@ -188,15 +895,37 @@ impl DrawContext {
         self.set_fill_mode(FillMode::Solid(color));
     }
 
+    /// Runs `f` with the specified fill mode, then restores the previous fill mode. Unlike
+    /// `push_state`/`pop_state`, any other changes made by `f` (such as to the transform) stay in
+    /// effect afterwards.
+    pub fn with_fill(&mut self, fill: FillMode, f: impl FnOnce(&mut DrawContext)) {
+        let old = std::mem::replace(&mut self.state.fill_mode, fill);
+        f(self);
+        self.state.fill_mode = old;
+    }
+
+    pub fn set_stroke_style(&mut self, new: StrokeStyle) {
+        self.state.stroke_style = new;
+    }
+
     pub fn get_layer_group_stack_size(&self) -> usize {
         self.layer_group_stack.len()
     }
 
-    pub fn set_layer(&mut self, height_index: i8) {
-        self.state.layer = height_index;
+    pub fn set_layer(&mut self, height: impl Into<LayerId>) {
+        let height = height.into();
+        height.debug_assert_not_reserved();
+        self.state.layer = height.0;
+    }
+
+    pub fn begin_layer_group(&mut self, height: impl Into<LayerId>) {
+        let height = height.into();
+        height.debug_assert_not_reserved();
+        self.begin_layer_group_impl(height.0);
     }
 
-    pub fn begin_layer_group(&mut self, height: i8) {
+    /// Like `begin_layer_group`, but allows reserved heights.
+    fn begin_layer_group_impl(&mut self, height: i8) {
         self.layer_group_stack.push((height, LayerGroup::new()));
         self.push_state();
         self.set_layer(0);
@@ -207,7 +936,11 @@ impl DrawContext {
     }
 
     pub fn end_layer_group(&mut self) {
-        debug_assert!(self.layer_group_stack.len() > 1);
+        // The root group is never popped.
+        assert!(
+            self.layer_group_stack.len() > 1,
+            "end_layer_group() called without a matching begin_layer_group()"
+        );
         let (height, group) = self.layer_group_stack.pop().unwrap();
         self.top_layer_group().add_subgroup(height, group);
         self.pop_state();
@@ -215,8 +948,14 @@ impl DrawContext {
 
     pub fn do_command(&mut self, command: RenderCommand) {
         let layer = self.state.layer;
-        self.top_layer_group()
-            .borrow_layer_mut(layer)
+        self.do_command_on_layer(layer, command);
+    }
+
+    fn do_command_on_layer(&mut self, layer: i8, command: RenderCommand) {
+        // Not using top_layer_group() so that the pool can be borrowed at the same time.
+        let group = &mut self.layer_group_stack.last_mut().unwrap().1;
+        group
+            .borrow_layer_mut(layer, &mut self.pool)
             .command_buffer
             .push(command);
     }
@@ -226,11 +965,21 @@ impl DrawContext {
         self.do_command(command);
     }
 
+    /// Fills a rectangle. Nothing is recorded if it has zero width or height, since it would not
+    /// cover anything. Negative sizes are not allowed.
     pub fn draw_rect(&mut self, top_left: impl Into<Point>, size: impl Into<Size>) {
         let top_left = top_left.into();
         let size = size.into();
+        debug_assert!(
+            size.x >= 0.0 && size.y >= 0.0,
+            "draw_rect() called with negative size {:?}",
+            size
+        );
+        if size.x == 0.0 || size.y == 0.0 {
+            return;
+        }
         let command = RenderCommand::DrawRect {
-            transform: self.state.transform.clone(),
+            transform: self.state.transform,
             top_left,
             size,
             fill: self.state.fill_mode.clone(),
@@ -238,76 +987,539 @@ impl DrawContext {
         self.do_command(command);
     }
 
-    fn finalize(self) -> LayerGroup {
-        debug_assert_eq!(self.layer_group_stack.len(), 1);
-        self.layer_group_stack.into_iter().next().unwrap().1
+    pub fn stroke_rect(&mut self, top_left: impl Into<Point>, size: impl Into<Size>) {
+        let top_left = top_left.into();
+        let size = size.into();
+        let command = RenderCommand::StrokeRect {
+            transform: self.state.transform,
+            top_left,
+            size,
+            stroke: self.state.stroke_style.clone(),
+            fill: self.state.fill_mode.clone(),
+        };
+        self.do_command(command);
     }
-}
-
-pub trait GuiConfig {
-    type Renderer;
-}
 
-#[derive(Clone, Copy)]
-pub struct SizeConstraint {
-    pub min: Size,
-    pub max: Size,
-}
+    pub fn draw_path(&mut self, points: Vec<Point>, closed: bool) {
+        let command = RenderCommand::DrawPath {
+            transform: self.state.transform,
+            points,
+            closed,
+            stroke: self.state.stroke_style.clone(),
+            fill: self.path_fill(),
+        };
+        self.do_command(command);
+    }
 
-impl SizeConstraint {
-    pub fn with_min(self, min: impl Into<Size>) -> Self {
-        Self {
-            min: min.into(),
-            max: self.max,
+    fn path_fill(&self) -> Option<FillMode> {
+        match self.state.fill_mode {
+            FillMode::None => None,
+            ref fill => Some(fill.clone()),
         }
     }
 
-    pub fn with_min_width(self, value: f32) -> Self {
-        self.with_min((value, self.min.y))
+    /// Draws a shadow for a rectangle on the layer below the current one, so that it appears
+    /// beneath whatever is drawn on the current layer. That layer must not be reserved, so shadows
+    /// can't be drawn from `LayerId::MIN_APP`.
+    pub fn draw_shadow(
+        &mut self,
+        top_left: impl Into<Point>,
+        size: impl Into<Size>,
+        blur: f32,
+        offset: impl Into<Vec2>,
+        color: Color,
+    ) {
+        let command = RenderCommand::DrawShadow {
+            transform: self.state.transform,
+            top_left: top_left.into(),
+            size: size.into(),
+            blur,
+            offset: offset.into(),
+            color,
+        };
+        let layer = self.state.layer.saturating_sub(1);
+        LayerId(layer).debug_assert_not_reserved();
+        self.do_command_on_layer(layer, command);
     }
 
-    pub fn with_min_height(self, value: f32) -> Self {
-        self.with_min((self.min.x, value))
+    /// Hides anything drawn on the current layer outside of the specified rectangle, until the
+    /// matching call to `pop_clip`. Other layers are not affected.
+    pub fn push_clip(&mut self, top_left: impl Into<Point>, size: impl Into<Size>) {
+        let command = RenderCommand::PushClip {
+            transform: self.state.transform,
+            top_left: top_left.into(),
+            size: size.into(),
+        };
+        self.do_command(command);
     }
 
-    pub fn with_max(self, max: impl Into<Size>) -> Self {
-        Self {
-            min: self.min,
-            max: max.into(),
-        }
+    pub fn pop_clip(&mut self) {
+        self.do_command(RenderCommand::PopClip);
     }
 
-    pub fn with_max_width(self, value: f32) -> Self {
-        self.with_max((value, self.max.y))
+    pub fn path_move_to(&mut self, point: impl Into<Point>) {
+        self.path.push(PathSegment::MoveTo(point.into()));
     }
 
-    pub fn with_max_height(self, value: f32) -> Self {
-        self.with_max((self.max.x, value))
+    pub fn path_line_to(&mut self, point: impl Into<Point>) {
+        self.path.push(PathSegment::LineTo(point.into()));
     }
-}
 
-pub trait RenderWidget<C: GuiConfig> {
-    fn layout(&mut self, constraint: SizeConstraint) -> Size;
-    fn draw(&self, drawer: &mut DrawContext);
-}
+    pub fn path_quad_to(&mut self, control: impl Into<Point>, end: impl Into<Point>) {
+        self.path
+            .push(PathSegment::QuadTo(control.into(), end.into()));
+    }
 
-pub enum Alignment {
-    Start,
-    Middle,
-    End,
-}
+    pub fn path_cubic_to(
+        &mut self,
+        control1: impl Into<Point>,
+        control2: impl Into<Point>,
+        end: impl Into<Point>,
+    ) {
+        self.path.push(PathSegment::CubicTo(
+            control1.into(),
+            control2.into(),
+            end.into(),
+        ));
+    }
 
-pub use Alignment::End as Right;
-pub use Alignment::End as Bottom;
-pub use Alignment::Middle;
-pub use Alignment::Middle as Center;
-pub use Alignment::Start as Left;
-pub use Alignment::Start as Top;
+    pub fn path_close(&mut self) {
+        self.path.push(PathSegment::Close);
+    }
+
+    /// Draws the path built by the previous `path_*` calls and starts a new, empty path.
+    pub fn finish_path(&mut self) {
+        let command = RenderCommand::DrawBezierPath {
+            transform: self.state.transform,
+            segments: std::mem::take(&mut self.path),
+            stroke: self.state.stroke_style.clone(),
+            fill: self.path_fill(),
+        };
+        self.do_command(command);
+    }
+
+    /// Every begin_layer_group() must have been matched by an end_layer_group() and every
+    /// push_state() by a pop_state() by the time drawing is finished.
+    fn finalize(self) -> LayerGroup {
+        debug_assert_eq!(self.layer_group_stack.len(), 1);
+        debug_assert_eq!(
+            self.state_stack.len(),
+            0,
+            "push_state() was called more times than pop_state()"
+        );
+        self.layer_group_stack.into_iter().next().unwrap().1
+    }
+}
+
+/// Default colors and metrics used by built-in widgets when they are not given explicitly.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub foreground: Color,
+    pub background: Color,
+    /// The basic unit of space between widgets.
+    pub spacing: f32,
+    pub border_radius: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            foreground: Color::WHITE,
+            background: Color::BLACK,
+            spacing: 8.0,
+            border_radius: 4.0,
+        }
+    }
+}
+
+pub trait GuiConfig {
+    /// The backend drawn layers are sent to, see `execute`.
+    type Renderer: Renderer;
+    /// Lets widgets find out how large content drawn by the backend will be during layout.
+    type Measurer: Measurer;
+
+    fn theme() -> Theme {
+        Theme::default()
+    }
+}
+
+/// A backend which draws render commands, see `execute`. Only `clear` and `fill_rect` have to be
+/// implemented, everything else is skipped unless the backend supports it.
+pub trait Renderer {
+    fn clear(&mut self, fill: &FillMode);
+    fn fill_rect(&mut self, transform: Transform, rect: Rect, fill: &FillMode);
+
+    fn stroke_rect(
+        &mut self,
+        _transform: Transform,
+        _rect: Rect,
+        _stroke: &StrokeStyle,
+        _fill: &FillMode,
+    ) {
+    }
+
+    fn draw_path(
+        &mut self,
+        _transform: Transform,
+        _points: &[Point],
+        _closed: bool,
+        _stroke: &StrokeStyle,
+        _fill: Option<&FillMode>,
+    ) {
+    }
+
+    fn draw_bezier_path(
+        &mut self,
+        _transform: Transform,
+        _segments: &[PathSegment],
+        _stroke: &StrokeStyle,
+        _fill: Option<&FillMode>,
+    ) {
+    }
+
+    fn draw_shadow(
+        &mut self,
+        _transform: Transform,
+        _rect: Rect,
+        _blur: f32,
+        _offset: Vec2,
+        _color: Color,
+    ) {
+    }
+
+    fn push_clip(&mut self, _transform: Transform, _rect: Rect) {}
+    fn pop_clip(&mut self) {}
+}
+
+/// For configurations which don't draw anything, e.g. in tests.
+impl Renderer for () {
+    fn clear(&mut self, _fill: &FillMode) {}
+    fn fill_rect(&mut self, _transform: Transform, _rect: Rect, _fill: &FillMode) {}
+}
+
+/// Sends every command in the provided layers to `renderer`, bottom layer first.
+pub fn execute(layers: &[Layer], renderer: &mut impl Renderer) {
+    for command in layers.iter().flat_map(|layer| layer.borrow_commands()) {
+        match command {
+            RenderCommand::Clear(fill) => renderer.clear(fill),
+            RenderCommand::DrawRect {
+                transform,
+                top_left,
+                size,
+                fill,
+            } => renderer.fill_rect(*transform, Rect::new(*top_left, *size), fill),
+            RenderCommand::StrokeRect {
+                transform,
+                top_left,
+                size,
+                stroke,
+                fill,
+            } => renderer.stroke_rect(*transform, Rect::new(*top_left, *size), stroke, fill),
+            RenderCommand::DrawPath {
+                transform,
+                points,
+                closed,
+                stroke,
+                fill,
+            } => renderer.draw_path(*transform, points, *closed, stroke, fill.as_ref()),
+            RenderCommand::DrawBezierPath {
+                transform,
+                segments,
+                stroke,
+                fill,
+            } => renderer.draw_bezier_path(*transform, segments, stroke, fill.as_ref()),
+            RenderCommand::DrawShadow {
+                transform,
+                top_left,
+                size,
+                blur,
+                offset,
+                color,
+            } => renderer.draw_shadow(
+                *transform,
+                Rect::new(*top_left, *size),
+                *blur,
+                *offset,
+                *color,
+            ),
+            RenderCommand::PushClip {
+                transform,
+                top_left,
+                size,
+            } => renderer.push_clip(*transform, Rect::new(*top_left, *size)),
+            RenderCommand::PopClip => renderer.pop_clip(),
+        }
+    }
+}
+
+/// Reports the sizes of content whose size depends on the backend, such as text.
+pub trait Measurer {
+    /// Returns the size of `text` when drawn at the specified font size.
+    fn measure_text(&self, text: &str, size: f32) -> Size;
+}
+
+/// For configurations which never measure anything. Everything is reported to be empty.
+impl Measurer for () {
+    fn measure_text(&self, _text: &str, _size: f32) -> Size {
+        Size::new(0.0, 0.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeConstraint {
+    pub min: Size,
+    pub max: Size,
+}
+
+impl SizeConstraint {
+    pub fn new(min: impl Into<Size>, max: impl Into<Size>) -> Self {
+        let (min, max) = (min.into(), max.into());
+        debug_assert!(
+            min.x <= max.x && min.y <= max.y,
+            "SizeConstraint minimum {:?} is larger than maximum {:?}",
+            min,
+            max
+        );
+        Self { min, max }
+    }
+
+    /// A constraint which only allows exactly the specified size.
+    pub fn tight(size: impl Into<Size>) -> Self {
+        let size = size.into();
+        Self::new(size, size)
+    }
+
+    /// Same as `new`, named to read well next to `exactly`.
+    pub fn from_sizes(min: impl Into<Size>, max: impl Into<Size>) -> Self {
+        Self::new(min, max)
+    }
+
+    /// Same as `tight`.
+    pub fn exactly(size: impl Into<Size>) -> Self {
+        Self::tight(size)
+    }
+
+    /// A constraint which allows any size from zero up to the specified maximum.
+    pub fn loose(max: impl Into<Size>) -> Self {
+        Self::new(0, max)
+    }
+
+    pub fn with_min(self, min: impl Into<Size>) -> Self {
+        Self {
+            min: min.into(),
+            max: self.max,
+        }
+    }
+
+    pub fn with_min_width(self, value: f32) -> Self {
+        self.with_min((value, self.min.y))
+    }
+
+    pub fn with_min_height(self, value: f32) -> Self {
+        self.with_min((self.min.x, value))
+    }
+
+    pub fn with_max(self, max: impl Into<Size>) -> Self {
+        Self {
+            min: self.min,
+            max: max.into(),
+        }
+    }
+
+    pub fn with_max_width(self, value: f32) -> Self {
+        self.with_max((value, self.max.y))
+    }
+
+    pub fn with_max_height(self, value: f32) -> Self {
+        self.with_max((self.max.x, value))
+    }
+
+    /// Returns the size closest to the provided one which satisfies this constraint. Widgets
+    /// should pass whatever size they would like to be through this before returning it from
+    /// `layout`.
+    pub fn constrain(&self, size: impl Into<Size>) -> Size {
+        let size = size.into();
+        Size::new(
+            size.x.clamp(self.min.x, self.max.x),
+            size.y.clamp(self.min.y, self.max.y),
+        )
+    }
+
+    /// True if only a single size satisfies this constraint.
+    pub fn is_tight(&self) -> bool {
+        self.min == self.max
+    }
+
+    /// True if the maximum size is finite on both axes. Containers like `Column` lay their
+    /// children out with an unbounded maximum height.
+    pub fn is_bounded(&self) -> bool {
+        self.max.x.is_finite() && self.max.y.is_finite()
+    }
+}
+
+pub trait RenderWidget<C: GuiConfig> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size;
+    fn draw(&self, drawer: &mut DrawContext);
+
+    /// How far below the top of this widget its content should be lined up with the content of
+    /// its neighbors, e.g. where the bottom of the letters of a line of text are. Only valid after
+    /// `layout`. Widgets that wrap a single child should pass on the child's baseline.
+    fn baseline(&self) -> Option<f32> {
+        None
+    }
+
+    /// True if this widget or one of its children has changed in a way that needs a new layout
+    /// since the last time it was laid out, see `LayoutCache`. Widgets that wrap children should
+    /// return true if any of their children are dirty.
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+/// A widget which sits at the top of the widget tree. Unlike other widgets, it is told when it
+/// starts and stops being shown, e.g. to set up and dispose of reactions. Wrap it in a `Root` to
+/// have these called.
+pub trait RootWidget<C: GuiConfig>: RenderWidget<C> {
+    /// Called by `Root` before the widget is laid out for the first time.
+    fn mount(&mut self) {}
+    /// Called by `Root::unmount` once the widget will no longer be shown.
+    fn unmount(&mut self) {}
+}
+
+/// Remembers whether a root widget is mounted, so that it is mounted exactly once before it is
+/// first laid out, no matter which `GuiDrawer` lays it out.
+pub struct Root<W> {
+    mounted: bool,
+    widget: W,
+}
+
+impl<W> Root<W> {
+    pub fn new<C: GuiConfig>(widget: W) -> Self
+    where
+        W: RootWidget<C>,
+    {
+        Self {
+            mounted: false,
+            widget,
+        }
+    }
+
+    pub fn widget(&self) -> &W {
+        &self.widget
+    }
+
+    pub fn widget_mut(&mut self) -> &mut W {
+        &mut self.widget
+    }
+
+    pub fn is_mounted(&self) -> bool {
+        self.mounted
+    }
+
+    /// Unmounts the widget if it is mounted. Laying it out again will mount it again.
+    pub fn unmount<C: GuiConfig>(&mut self)
+    where
+        W: RootWidget<C>,
+    {
+        if self.mounted {
+            self.mounted = false;
+            self.widget.unmount();
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RootWidget<C>> RenderWidget<C> for Root<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        if !self.mounted {
+            self.mounted = true;
+            self.widget.mount();
+        }
+        self.widget.layout(constraint, measurer)
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        self.widget.draw(drawer);
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.widget.baseline()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.widget.is_dirty()
+    }
+}
+
+pub enum Alignment {
+    Start,
+    Middle,
+    End,
+}
+
+pub use Alignment::End as Right;
+pub use Alignment::End as Bottom;
+pub use Alignment::Middle;
+pub use Alignment::Middle as Center;
+pub use Alignment::Start as Left;
+pub use Alignment::Start as Top;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+/// Picks where to put the top left corner of a popup (like a tooltip or dropdown) attached to
+/// `anchor`. The popup goes on the `preferred` side of the anchor unless it would not fit on the
+/// screen there, in which case it goes on the opposite side if it fits there instead. Finally, the
+/// position is moved as little as possible to keep the popup on the screen.
+pub fn position_popup(anchor: Rect, popup_size: Size, screen: Size, preferred: Side) -> Point {
+    let place = |side: Side| {
+        let (top_left, bottom_right) = (anchor.top_left, anchor.bottom_right());
+        match side {
+            Side::Top => Point::new(top_left.x, top_left.y - popup_size.y),
+            Side::Bottom => Point::new(top_left.x, bottom_right.y),
+            Side::Left => Point::new(top_left.x - popup_size.x, top_left.y),
+            Side::Right => Point::new(bottom_right.x, top_left.y),
+        }
+    };
+    let fits = |position: Point| {
+        let end = position + popup_size;
+        position.x >= 0.0 && position.y >= 0.0 && end.x <= screen.x && end.y <= screen.y
+    };
+    let mut position = place(preferred);
+    let flipped = place(preferred.opposite());
+    if !fits(position) && fits(flipped) {
+        position = flipped;
+    }
+    // If the popup is bigger than the screen, keep its top left corner visible.
+    let max = screen - popup_size;
+    Point::new(
+        position.x.min(max.x).max(0.0),
+        position.y.min(max.y).max(0.0),
+    )
+}
 
 pub struct AlignBox<W> {
     pub horizontal: Alignment,
     pub vertical: Alignment,
     child_pos: Point,
+    child_size: Size,
+    /// Drawing before `child_pos` has been computed would silently put the child in the corner.
+    laid_out: bool,
     child: W,
 }
 
@@ -320,15 +1532,18 @@ impl<W> AlignBox<W> {
             horizontal,
             vertical,
             child_pos: 0.into(),
+            child_size: 0.into(),
+            laid_out: false,
             child,
         }
     }
 }
 
 impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for AlignBox<W> {
-    fn layout(&mut self, constraint: SizeConstraint) -> Size {
-        let child_size = self.child.layout(constraint.with_min(0));
-        println!("{:?}", child_size);
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let child_size = self.child.layout(constraint.with_min(0), measurer);
+        self.child_size = child_size;
+        self.laid_out = true;
         self.child_pos.x = match self.horizontal {
             Alignment::Start => 0.0,
             Alignment::Middle => (constraint.max.x - child_size.x) / 2.0,
@@ -343,81 +1558,1142 @@ impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for AlignBox<W> {
     }
 
     fn draw(&self, drawer: &mut DrawContext) {
-        drawer.draw_child(&self.child, self.child_pos);
+        debug_assert!(
+            self.laid_out,
+            "AlignBox was drawn before it was laid out, make sure layout() is called before draw()"
+        );
+        drawer.draw_child(&self.child, self.child_pos, self.child_size);
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.child
+            .baseline()
+            .map(|baseline| baseline + self.child_pos.y)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
     }
 }
 
-pub struct DebugRect;
+/// Leaves the specified amount of empty space around all sides of its child.
+pub struct Padding<W> {
+    pub amount: f32,
+    child_size: Size,
+    child: W,
+}
 
-impl<C: GuiConfig> RenderWidget<C> for DebugRect {
-    fn layout(&mut self, _constraint: SizeConstraint) -> Size {
-        Size::new(100.0, 100.0)
+impl<W> Padding<W> {
+    pub fn new<C: GuiConfig>(amount: f32, child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self {
+            amount,
+            child_size: 0.into(),
+            child,
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Padding<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let total = self.amount * 2.0;
+        let shrink = |value: f32| (value - total).max(0.0);
+        let child_constraint = SizeConstraint::new(
+            (shrink(constraint.min.x), shrink(constraint.min.y)),
+            (shrink(constraint.max.x), shrink(constraint.max.y)),
+        );
+        self.child_size = self.child.layout(child_constraint, measurer);
+        constraint.constrain(self.child_size + total)
     }
 
     fn draw(&self, drawer: &mut DrawContext) {
-        drawer.fill_solid_color(Color::MAGENTA);
-        drawer.draw_rect(0, (100, 100));
+        drawer.draw_child(&self.child, self.amount, self.child_size);
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.child.baseline().map(|baseline| baseline + self.amount)
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
     }
 }
 
-pub struct Column<W> {
-    children: Vec<(f32, W)>,
+/// Makes its child fill all the space offered by the parent.
+pub struct Expand<W> {
+    size: Size,
+    child: W,
 }
 
-impl<W> Column<W> {
-    pub fn new<C>(children: Vec<W>) -> Self
+impl<W> Expand<W> {
+    pub fn new<C: GuiConfig>(child: W) -> Self
     where
-        C: GuiConfig,
         W: RenderWidget<C>,
     {
         Self {
-            children: children.into_iter().map(|child| (0.0, child)).collect(),
+            size: 0.into(),
+            child,
         }
     }
 }
 
-impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Column<W> {
-    fn layout(&mut self, constraint: SizeConstraint) -> Size {
-        let mut width = constraint.min.x;
-        let mut total_height = 0.0;
-        let child_constraint = constraint.with_max_height(std::f32::INFINITY);
-        for (height, child) in self.children.iter_mut() {
-            let child_size = child.layout(child_constraint);
-            *height = child_size.y;
-            total_height += *height;
-            width = width.max(child_size.x);
-        }
-        Size::new(width, total_height)
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Expand<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        self.size = constraint.max;
+        self.child
+            .layout(SizeConstraint::tight(self.size), measurer);
+        self.size
     }
 
     fn draw(&self, drawer: &mut DrawContext) {
-        let mut offset = 0.0;
-        for (height, child) in self.children.iter() {
-            drawer.draw_child(child, (0.0, offset));
-            offset += *height;
+        drawer.draw_child(&self.child, 0, self.size);
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
+    }
+}
+
+/// Hides anything its child draws outside of the bounds it was laid out to. Only the layer the
+/// clip box itself is drawn on is clipped.
+pub struct ClipBox<W> {
+    size: Size,
+    child: W,
+}
+
+impl<W> ClipBox<W> {
+    pub fn new<C: GuiConfig>(child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self {
+            size: 0.into(),
+            child,
         }
     }
 }
 
-pub struct GuiDrawer;
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for ClipBox<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        self.size = self.child.layout(constraint, measurer);
+        self.size
+    }
 
-impl GuiDrawer {
-    pub fn new() -> Self {
-        Self {}
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.push_clip(0, self.size);
+        drawer.draw_child(&self.child, 0, self.size);
+        drawer.pop_clip();
     }
 
-    pub fn layout<C: GuiConfig, R: RenderWidget<C>>(&self, widget: &mut R) {
-        let screen_size = Size::new(800.0, 600.0);
-        let screen_constraint = SizeConstraint {
-            min: screen_size,
-            max: screen_size,
+    fn baseline(&self) -> Option<f32> {
+        self.child.baseline()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
+    }
+}
+
+/// How a `FittedBox` fits its child into the available space.
+pub type BoxFit = ImageFit;
+
+/// Lays out its child at whatever size it likes, then scales it to fit the space offered by the
+/// parent. Cropping fits like `BoxFit::Cover` clip the child to the box.
+pub struct FittedBox<W> {
+    pub fit: BoxFit,
+    size: Size,
+    child_size: Size,
+    child: W,
+}
+
+impl<W> FittedBox<W> {
+    pub fn new<C: GuiConfig>(fit: BoxFit, child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self {
+            fit,
+            size: 0.into(),
+            child_size: 0.into(),
+            child,
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for FittedBox<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let unbounded = Size::new(f32::INFINITY, f32::INFINITY);
+        self.child_size = self
+            .child
+            .layout(SizeConstraint::loose(unbounded), measurer);
+        self.size = if constraint.is_bounded() {
+            constraint.max
+        } else {
+            constraint.constrain(self.child_size)
         };
-        widget.layout(screen_constraint);
+        self.size
     }
 
-    pub fn draw<C: GuiConfig, R: RenderWidget<C>>(&self, widget: &R) -> Vec<Layer> {
-        let mut context = DrawContext::new();
-        widget.draw(&mut context);
-        context.finalize().flatten()
+    fn draw(&self, drawer: &mut DrawContext) {
+        let (source, dest) = self
+            .fit
+            .compute_rects(self.child_size, Rect::new(0, self.size));
+        let fit = match Transform::from_rect_to_rect(source, dest) {
+            Some(fit) => fit,
+            None => return,
+        };
+        let cropped = source.size != self.child_size;
+        if cropped {
+            drawer.push_clip(0, self.size);
+        }
+        drawer.push_state();
+        drawer.apply_transform(fit);
+        drawer.draw_child(&self.child, 0, self.child_size);
+        drawer.pop_state();
+        if cropped {
+            drawer.pop_clip();
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
+    }
+}
+
+/// Skips laying out its child again if the constraint is the same as last time and nothing inside
+/// it is dirty, reusing the size from the previous layout instead. The child can only be modified
+/// through `child_mut` or after `mark_dirty`, which makes this and every `LayoutCache` containing it
+/// dirty until the next layout.
+pub struct LayoutCache<W> {
+    dirty: bool,
+    last_layout: Option<(SizeConstraint, Size)>,
+    child: W,
+}
+
+impl<W> LayoutCache<W> {
+    pub fn new<C: GuiConfig>(child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self {
+            dirty: true,
+            last_layout: None,
+            child,
+        }
+    }
+
+    /// Makes the next layout lay out the child again.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn child(&self) -> &W {
+        &self.child
+    }
+
+    /// Marks this cache as dirty, since the child might be changed through the returned reference.
+    pub fn child_mut(&mut self) -> &mut W {
+        self.mark_dirty();
+        &mut self.child
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for LayoutCache<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        if let Some((last_constraint, size)) = self.last_layout {
+            if last_constraint == constraint && !RenderWidget::<C>::is_dirty(self) {
+                return size;
+            }
+        }
+        let size = self.child.layout(constraint, measurer);
+        self.dirty = false;
+        self.last_layout = Some((constraint, size));
+        size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        let size = self.last_layout.map_or_else(|| 0.into(), |(_, size)| size);
+        drawer.draw_child(&self.child, 0, size);
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.child.baseline()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty || self.child.is_dirty()
+    }
+}
+
+/// Remembers the size its child picked during the last layout.
+pub struct Measured<W> {
+    size: Option<Size>,
+    child: W,
+}
+
+impl<W> Measured<W> {
+    pub fn new<C: GuiConfig>(child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self { size: None, child }
+    }
+
+    /// The size of the child, or `None` if it has not been laid out yet.
+    pub fn size(&self) -> Option<Size> {
+        self.size
+    }
+
+    pub fn child(&self) -> &W {
+        &self.child
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Measured<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let size = self.child.layout(constraint, measurer);
+        self.size = Some(size);
+        size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.draw_child(&self.child, 0, self.size.unwrap_or_else(|| 0.into()));
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.child.baseline()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
+    }
+}
+
+/// Remembers what its child drew the first time it was drawn and reuses that instead of drawing the
+/// child again, until `invalidate` is called or the child's size changes. The child is recorded
+/// with a fresh drawing state and its output is always placed above anything else on the layer
+/// it is drawn on. Overlays drawn by the child are not shown above the rest of the UI.
+pub struct CachedDraw<W> {
+    size: Option<Size>,
+    cache: RefCell<Option<LayerGroup>>,
+    child: W,
+}
+
+impl<W> CachedDraw<W> {
+    pub fn new<C: GuiConfig>(child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self {
+            size: None,
+            cache: RefCell::new(None),
+            child,
+        }
+    }
+
+    /// Makes the next draw call draw the child again.
+    pub fn invalidate(&self) {
+        self.cache.replace(None);
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for CachedDraw<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let size = self.child.layout(constraint, measurer);
+        if self.size != Some(size) {
+            self.invalidate();
+        }
+        self.size = Some(size);
+        size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        let mut cache = self.cache.borrow_mut();
+        let recorded = cache.get_or_insert_with(|| {
+            let mut recorder = DrawContext::new();
+            recorder.debug_outlines = drawer.debug_outlines;
+            self.child.draw(&mut recorder);
+            recorder.finalize()
+        });
+        drawer.draw_recorded(recorded.clone());
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.child.baseline()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
+    }
+}
+
+/// Draws a solid background behind its child. Uses the theme's background color if no color is
+/// specified.
+pub struct Container<W> {
+    pub color: Option<Color>,
+    size: Size,
+    child: W,
+}
+
+impl<W> Container<W> {
+    pub fn new<C: GuiConfig>(color: impl Into<Option<Color>>, child: W) -> Self
+    where
+        W: RenderWidget<C>,
+    {
+        Self {
+            color: color.into(),
+            size: 0.into(),
+            child,
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Container<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        self.size = self.child.layout(constraint, measurer);
+        self.size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        let color = self.color.unwrap_or_else(|| C::theme().background);
+        drawer.fill_solid_color(color);
+        drawer.draw_rect(0, self.size);
+        drawer.draw_child(&self.child, 0, self.size);
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.child.baseline()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.child.is_dirty()
+    }
+}
+
+/// `None` takes up no space and draws nothing.
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Option<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        match self {
+            Some(child) => child.layout(constraint, measurer),
+            None => constraint.constrain(0),
+        }
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        if let Some(child) = self {
+            child.draw(drawer);
+        }
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.as_ref().and_then(|child| child.baseline())
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.iter().any(|child| child.is_dirty())
+    }
+}
+
+/// Shows the output of a separate draw, e.g. of a sub-UI which was laid out on its own. Always
+/// takes up exactly `size`.
+pub struct SubScene {
+    pub size: Size,
+    pub layers: Vec<Layer>,
+}
+
+impl SubScene {
+    pub fn new(size: impl Into<Size>, layers: Vec<Layer>) -> Self {
+        Self {
+            size: size.into(),
+            layers,
+        }
+    }
+}
+
+impl<C: GuiConfig> RenderWidget<C> for SubScene {
+    fn layout(&mut self, _constraint: SizeConstraint, _measurer: &C::Measurer) -> Size {
+        self.size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.splice_layers(self.layers.clone());
+    }
+}
+
+/// A magenta rectangle which would like to be 100x100.
+pub struct DebugRect {
+    size: Size,
+}
+
+impl DebugRect {
+    pub fn new() -> Self {
+        Self { size: 0.into() }
+    }
+}
+
+impl Default for DebugRect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: GuiConfig> RenderWidget<C> for DebugRect {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &C::Measurer) -> Size {
+        self.size = constraint.constrain((100, 100));
+        self.size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.fill_solid_color(Color::MAGENTA);
+        drawer.draw_rect(0, self.size);
+    }
+}
+
+pub struct Checkbox {
+    pub checked: bool,
+    /// The size the checkbox would like to be, the constraint can still make it smaller or larger.
+    pub size: f32,
+    laid_out_size: Size,
+}
+
+impl Checkbox {
+    pub fn new(checked: bool, size: f32) -> Self {
+        Self {
+            checked,
+            size,
+            laid_out_size: 0.into(),
+        }
+    }
+}
+
+impl<C: GuiConfig> RenderWidget<C> for Checkbox {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &C::Measurer) -> Size {
+        self.laid_out_size = constraint.constrain(self.size);
+        self.laid_out_size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        let size = self.laid_out_size;
+        drawer.fill_solid_color(C::theme().foreground);
+        drawer.stroke_rect(0, size);
+        if self.checked {
+            let inset = size / 4.0;
+            drawer.draw_rect(inset, size - inset * 2.0);
+        }
+    }
+}
+
+/// Takes up all the width it is allowed to. If the maximum width is unbounded, e.g. inside a `Row`,
+/// it only takes up the minimum width instead.
+pub struct ProgressBar {
+    /// How much of the bar is filled, from 0 to 1. Values that are not finite count as 0.
+    pub progress: f32,
+    pub track: Color,
+    pub fill: Color,
+    pub height: f32,
+    /// The size picked during the last layout.
+    size: Size,
+}
+
+impl ProgressBar {
+    pub fn new(progress: f32, track: Color, fill: Color, height: f32) -> Self {
+        Self {
+            progress,
+            track,
+            fill,
+            height,
+            size: 0.into(),
+        }
+    }
+}
+
+impl<C: GuiConfig> RenderWidget<C> for ProgressBar {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &C::Measurer) -> Size {
+        let width = if constraint.max.x.is_finite() {
+            constraint.max.x
+        } else {
+            constraint.min.x
+        };
+        self.size = constraint.constrain((width, self.height));
+        self.size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.fill_solid_color(self.track);
+        drawer.draw_rect(0, self.size);
+        drawer.fill_solid_color(self.fill);
+        let progress = if self.progress.is_finite() {
+            self.progress.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let filled = self.size.x * progress;
+        drawer.draw_rect(0, (filled, self.size.y));
+    }
+}
+
+/// What a widget does with children that don't fit inside of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Children are drawn in full, even outside the widget.
+    Visible,
+    /// Anything outside the widget is hidden, see `DrawContext::push_clip`.
+    Clip,
+}
+
+pub struct Column<W> {
+    children: Vec<(Size, W)>,
+    /// The size picked during the last layout.
+    size: Size,
+    pub overflow: Overflow,
+}
+
+impl<W> Column<W> {
+    pub fn new<C>(children: Vec<W>) -> Self
+    where
+        C: GuiConfig,
+        W: RenderWidget<C>,
+    {
+        children.into_iter().collect()
+    }
+
+    /// Creates a column whose children are identified by keys instead of by their position. See
+    /// `KeyedColumn::rebuild`.
+    pub fn keyed<C, K>(children: Vec<(K, W)>) -> KeyedColumn<K, W>
+    where
+        C: GuiConfig,
+        W: RenderWidget<C>,
+        K: Eq + Hash,
+    {
+        let (keys, children) = children.into_iter().unzip();
+        KeyedColumn {
+            keys,
+            column: Self::new(children),
+        }
+    }
+
+    /// Adds a child to the bottom of the column.
+    pub fn push(&mut self, child: W) {
+        self.children.push((0.into(), child));
+    }
+
+    pub fn with_overflow(self, overflow: Overflow) -> Self {
+        Self { overflow, ..self }
+    }
+}
+
+impl<W> Column<Container<W>> {
+    /// Creates a column where each child has a background, alternating between the two colors
+    /// starting with `even_color` for the first child.
+    pub fn zebra<C>(children: Vec<W>, even_color: Color, odd_color: Color) -> Self
+    where
+        C: GuiConfig,
+        W: RenderWidget<C>,
+    {
+        children
+            .into_iter()
+            .enumerate()
+            .map(|(index, child)| {
+                let color = if index % 2 == 0 {
+                    even_color
+                } else {
+                    odd_color
+                };
+                Container::new(color, child)
+            })
+            .collect()
+    }
+}
+
+impl<W> FromIterator<W> for Column<W> {
+    fn from_iter<I: IntoIterator<Item = W>>(children: I) -> Self {
+        Self {
+            children: children
+                .into_iter()
+                .map(|child| (0.into(), child))
+                .collect(),
+            size: 0.into(),
+            overflow: Overflow::Visible,
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Column<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let mut width = constraint.min.x;
+        let mut total_height = 0.0;
+        let child_constraint = constraint.with_max_height(f32::INFINITY);
+        for (size, child) in self.children.iter_mut() {
+            *size = child.layout(child_constraint, measurer);
+            total_height += size.y;
+            width = width.max(size.x);
+        }
+        self.size = constraint.constrain((width, total_height));
+        self.size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        let clip = self.overflow == Overflow::Clip;
+        if clip {
+            drawer.push_clip(0, self.size);
+        }
+        let mut offset = 0.0;
+        for (size, child) in self.children.iter() {
+            drawer.draw_child(child, (0.0, offset), *size);
+            offset += size.y;
+        }
+        if clip {
+            drawer.pop_clip();
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.children.iter().any(|(_, child)| child.is_dirty())
+    }
+}
+
+/// How children are placed perpendicular to the direction a container lays them out in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrossAxisAlignment {
+    Start,
+    /// Lines up the baselines of all children, see `RenderWidget::baseline`. Children without a
+    /// baseline are placed at the start instead.
+    Baseline,
+}
+
+/// Places its children next to each other from left to right.
+pub struct Row<W> {
+    /// Where each child was placed during the last layout.
+    children: Vec<(Rect, W)>,
+    fill_index: Option<usize>,
+    pub cross_axis_alignment: CrossAxisAlignment,
+    /// The baseline shared by the children during the last layout, if they were aligned by it.
+    baseline: Option<f32>,
+}
+
+impl<W> Row<W> {
+    pub fn new<C>(children: Vec<W>) -> Self
+    where
+        C: GuiConfig,
+        W: RenderWidget<C>,
+    {
+        Self {
+            children: children
+                .into_iter()
+                .map(|child| (Rect::new(0, 0), child))
+                .collect(),
+            fill_index: None,
+            cross_axis_alignment: CrossAxisAlignment::Start,
+            baseline: None,
+        }
+    }
+
+    pub fn with_cross_axis_alignment(self, cross_axis_alignment: CrossAxisAlignment) -> Self {
+        Self {
+            cross_axis_alignment,
+            ..self
+        }
+    }
+
+    /// Creates a row where the child at `fill_index` is stretched to take up whatever width the
+    /// other children don't need, pushing the children after it to the right edge. The maximum
+    /// width of the constraint must be finite.
+    pub fn with_fill<C>(children: Vec<W>, fill_index: usize) -> Self
+    where
+        C: GuiConfig,
+        W: RenderWidget<C>,
+    {
+        assert!(
+            fill_index < children.len(),
+            "fill_index {} is out of bounds for a row with {} children",
+            fill_index,
+            children.len()
+        );
+        Self {
+            fill_index: Some(fill_index),
+            ..Self::new(children)
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for Row<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        let child_constraint = constraint.with_min_width(0.0).with_max_width(f32::INFINITY);
+        let mut sizes = vec![Size::new(0.0, 0.0); self.children.len()];
+        let mut height = constraint.min.y;
+        for (index, (_, child)) in self.children.iter_mut().enumerate() {
+            if Some(index) != self.fill_index {
+                sizes[index] = child.layout(child_constraint, measurer);
+                height = height.max(sizes[index].y);
+            }
+        }
+        if let Some(index) = self.fill_index {
+            debug_assert!(
+                constraint.max.x.is_finite(),
+                "A Row with a fill child needs a bounded maximum width"
+            );
+            let remaining =
+                (constraint.max.x - sizes.iter().map(|size| size.x).sum::<f32>()).max(0.0);
+            let fill_constraint = child_constraint
+                .with_min_width(remaining)
+                .with_max_width(remaining);
+            sizes[index] = self.children[index].1.layout(fill_constraint, measurer);
+            height = height.max(sizes[index].y);
+        }
+        self.baseline = match self.cross_axis_alignment {
+            CrossAxisAlignment::Start => None,
+            CrossAxisAlignment::Baseline => self
+                .children
+                .iter()
+                .filter_map(|(_, child)| child.baseline())
+                .fold(None, |max: Option<f32>, baseline| {
+                    Some(max.map_or(baseline, |max| max.max(baseline)))
+                }),
+        };
+        let mut x = 0.0;
+        for ((bounds, child), size) in self.children.iter_mut().zip(sizes) {
+            let y = match (self.baseline, child.baseline()) {
+                (Some(shared), Some(own)) => shared - own,
+                _ => 0.0,
+            };
+            *bounds = Rect::new((x, y), size);
+            height = height.max(y + size.y);
+            x += size.x;
+        }
+        constraint.constrain((x, height))
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        for (bounds, child) in &self.children {
+            drawer.draw_child(child, bounds.top_left, bounds.size);
+        }
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        self.baseline
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.children.iter().any(|(_, child)| child.is_dirty())
+    }
+}
+
+/// Like `Column`, but every child gets an equal share of the available height no matter how big
+/// it would like to be. The maximum height of the constraint must be finite.
+pub struct EqualColumn<W> {
+    children: Vec<(Size, W)>,
+    share: f32,
+}
+
+impl<W> EqualColumn<W> {
+    pub fn new<C>(children: Vec<W>) -> Self
+    where
+        C: GuiConfig,
+        W: RenderWidget<C>,
+    {
+        Self {
+            children: children
+                .into_iter()
+                .map(|child| (0.into(), child))
+                .collect(),
+            share: 0.0,
+        }
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> RenderWidget<C> for EqualColumn<W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        debug_assert!(
+            constraint.max.y.is_finite(),
+            "EqualColumn needs a bounded maximum height to divide between its children"
+        );
+        self.share = constraint.max.y / self.children.len().max(1) as f32;
+        let child_constraint = SizeConstraint::new(
+            (constraint.min.x, self.share),
+            (constraint.max.x, self.share),
+        );
+        let mut width = constraint.min.x;
+        for (size, child) in &mut self.children {
+            *size = child.layout(child_constraint, measurer);
+            width = width.max(size.x);
+        }
+        constraint.constrain((width, constraint.max.y))
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        for (index, (size, child)) in self.children.iter().enumerate() {
+            drawer.draw_child(child, (0.0, index as f32 * self.share), *size);
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.children.iter().any(|(_, child)| child.is_dirty())
+    }
+}
+
+pub struct KeyedColumn<K, W> {
+    keys: Vec<K>,
+    column: Column<W>,
+}
+
+impl<K: Eq + Hash, W> KeyedColumn<K, W> {
+    /// Replaces the children of this column. If a new child has the same key as one of the old
+    /// children, the old widget is kept (along with any state it has, such as cached layout) and
+    /// the new one is discarded.
+    pub fn rebuild(&mut self, children: Vec<(K, W)>) {
+        let old_keys = self.keys.drain(..);
+        let old_children = self.column.children.drain(..);
+        let mut old: HashMap<K, (Size, W)> = old_keys.zip(old_children).collect();
+        for (key, child) in children {
+            let child = old.remove(&key).unwrap_or((0.into(), child));
+            self.keys.push(key);
+            self.column.children.push(child);
+        }
+    }
+}
+
+impl<C: GuiConfig, K, W: RenderWidget<C>> RenderWidget<C> for KeyedColumn<K, W> {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &C::Measurer) -> Size {
+        RenderWidget::<C>::layout(&mut self.column, constraint, measurer)
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        RenderWidget::<C>::draw(&self.column, drawer)
+    }
+
+    fn is_dirty(&self) -> bool {
+        RenderWidget::<C>::is_dirty(&self.column)
+    }
+}
+
+/// Shorthands for wrapping a widget in another one, so that widget trees can be written like
+/// `label.padded(8.0).aligned(Center, Middle)`. The config is inferred from the widget, so this
+/// only works for widgets which implement `RenderWidget` for a single config. Widgets like
+/// `DebugRect` which work with any config have to be wrapped with the regular constructors.
+pub trait WidgetExt<C: GuiConfig>: RenderWidget<C> + Sized {
+    fn padded(self, amount: f32) -> Padding<Self> {
+        Padding::new::<C>(amount, self)
+    }
+
+    fn aligned(self, horizontal: Alignment, vertical: Alignment) -> AlignBox<Self> {
+        AlignBox::new::<C>(horizontal, vertical, self)
+    }
+
+    fn background(self, color: Color) -> Container<Self> {
+        Container::new::<C>(color, self)
+    }
+}
+
+impl<C: GuiConfig, W: RenderWidget<C>> WidgetExt<C> for W {}
+
+/// Returns the smallest rectangle containing every rectangle drawn in the provided layers, or
+/// `None` if nothing was drawn. Paths and shadows are not taken into account.
+pub fn scene_bounds(layers: &[Layer]) -> Option<Rect> {
+    let mut result: Option<Rect> = None;
+    for command in layers.iter().flat_map(|layer| layer.borrow_commands()) {
+        let bounds = match command {
+            RenderCommand::DrawRect {
+                transform,
+                top_left,
+                size,
+                ..
+            }
+            | RenderCommand::StrokeRect {
+                transform,
+                top_left,
+                size,
+                ..
+            } => transform.transform_bounds(Rect::new(*top_left, *size)),
+            _ => continue,
+        };
+        result = Some(match result {
+            Some(result) => result.union(bounds),
+            None => bounds,
+        });
+    }
+    result
+}
+
+/// Describes how much work a drawn frame contains, see `GuiDrawer::draw_with_stats`.
+#[derive(Clone, Debug, Default)]
+pub struct RenderStats {
+    pub total_commands: usize,
+    pub num_layers: usize,
+    /// How many commands of each kind were produced, keyed by `RenderCommand::kind`.
+    pub counts_by_kind: HashMap<&'static str, usize>,
+}
+
+impl RenderStats {
+    pub fn from_layers(layers: &[Layer]) -> Self {
+        let mut stats = Self {
+            num_layers: layers.len(),
+            ..Default::default()
+        };
+        for command in layers.iter().flat_map(|layer| layer.borrow_commands()) {
+            stats.total_commands += 1;
+            *stats.counts_by_kind.entry(command.kind()).or_insert(0) += 1;
+        }
+        stats
+    }
+
+    /// How many commands of the specified kind were produced.
+    pub fn count(&self, kind: &str) -> usize {
+        self.counts_by_kind.get(kind).copied().unwrap_or(0)
+    }
+}
+
+/// Describes the provided layers in a compact, human readable form, with one line for each layer
+/// followed by one indented line for each of its commands. Only meant for debugging, the format
+/// may change at any time.
+pub fn debug_tree(layers: &[Layer]) -> String {
+    let mut result = String::new();
+    for (index, layer) in layers.iter().enumerate() {
+        result.push_str(&format!("Layer {}:\n", index));
+        for command in layer.borrow_commands() {
+            result.push_str(&format!("  {}\n", describe_command(command)));
+        }
+    }
+    result
+}
+
+fn describe_command(command: &RenderCommand) -> String {
+    let describe_fill = |fill: &FillMode| match fill {
+        FillMode::None => "none".to_owned(),
+        FillMode::Solid(color) => color.to_string(),
+    };
+    let describe_rect = |transform: &Transform, top_left: &Point, size: &Size| {
+        let origin = *top_left * *transform;
+        let mut result = format!("@({},{}) {}x{}", origin.x, origin.y, size.x, size.y);
+        if !transform.is_translation() {
+            result.push_str(" transformed");
+        }
+        result
+    };
+    let describe_path_fill = |fill: &Option<FillMode>| match fill {
+        Some(fill) => describe_fill(fill),
+        None => "none".to_owned(),
+    };
+    let kind = command.kind();
+    match command {
+        RenderCommand::Clear(fill) => format!("{} fill={}", kind, describe_fill(fill)),
+        RenderCommand::DrawRect {
+            transform,
+            top_left,
+            size,
+            fill,
+        } => format!(
+            "{} {} fill={}",
+            kind,
+            describe_rect(transform, top_left, size),
+            describe_fill(fill)
+        ),
+        RenderCommand::StrokeRect {
+            transform,
+            top_left,
+            size,
+            stroke,
+            fill,
+        } => format!(
+            "{} {} width={} fill={}",
+            kind,
+            describe_rect(transform, top_left, size),
+            stroke.width,
+            describe_fill(fill)
+        ),
+        RenderCommand::DrawPath {
+            points,
+            closed,
+            fill,
+            ..
+        } => format!(
+            "{} {} points{} fill={}",
+            kind,
+            points.len(),
+            if *closed { " closed" } else { "" },
+            describe_path_fill(fill)
+        ),
+        RenderCommand::DrawBezierPath { segments, fill, .. } => format!(
+            "{} {} segments fill={}",
+            kind,
+            segments.len(),
+            describe_path_fill(fill)
+        ),
+        RenderCommand::DrawShadow {
+            transform,
+            top_left,
+            size,
+            blur,
+            color,
+            ..
+        } => format!(
+            "{} {} blur={} color={}",
+            kind,
+            describe_rect(transform, top_left, size),
+            blur,
+            color
+        ),
+        RenderCommand::PushClip {
+            transform,
+            top_left,
+            size,
+        } => format!("{} {}", kind, describe_rect(transform, top_left, size)),
+        RenderCommand::PopClip => kind.to_owned(),
+    }
+}
+
+#[derive(Default)]
+pub struct GuiDrawer {
+    /// If true, an outline is drawn around every widget to help debug layout.
+    pub debug_outlines: bool,
+}
+
+impl GuiDrawer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn layout<C: GuiConfig, R: RenderWidget<C>>(&self, widget: &mut R, measurer: &C::Measurer) {
+        let screen_size = Size::new(800.0, 600.0);
+        let screen_constraint = SizeConstraint::tight(screen_size);
+        widget.layout(screen_constraint, measurer);
+    }
+
+    pub fn draw<C: GuiConfig, R: RenderWidget<C>>(&self, widget: &R) -> Vec<Layer> {
+        let mut context = DrawContext::new();
+        context.debug_outlines = self.debug_outlines;
+        widget.draw(&mut context);
+        context.finalize().flatten()
+    }
+
+    /// Like `draw`, but the command buffers of the returned layers are taken from `pool` when
+    /// possible instead of being freshly allocated. Pass the layers of the previous frame to
+    /// `BufferPool::recycle` once they have been rendered.
+    pub fn draw_with_buffers<C: GuiConfig, R: RenderWidget<C>>(
+        &self,
+        widget: &R,
+        pool: &mut BufferPool,
+    ) -> Vec<Layer> {
+        let mut context = DrawContext::with_buffers(std::mem::take(pool));
+        context.debug_outlines = self.debug_outlines;
+        widget.draw(&mut context);
+        *pool = std::mem::take(&mut context.pool);
+        context.finalize().flatten()
+    }
+
+    /// Like `draw`, but also reports statistics about the commands that were produced.
+    pub fn draw_with_stats<C: GuiConfig, R: RenderWidget<C>>(
+        &self,
+        widget: &R,
+    ) -> (Vec<Layer>, RenderStats) {
+        let layers = self.draw(widget);
+        let stats = RenderStats::from_layers(&layers);
+        (layers, stats)
+    }
+
+    /// Draws the widget and sends the result straight to the configuration's renderer.
+    pub fn render<C: GuiConfig, R: RenderWidget<C>>(&self, widget: &R, renderer: &mut C::Renderer) {
+        execute(&self.draw(widget), renderer);
     }
 }
@@ -0,0 +1,82 @@
+//! A minimal renderer which draws scenes into a pixel buffer on the CPU. Useful when no GPU
+//! backend is available and for comparing rendered output in tests.
+
+use crate::{Color, FillMode, Layer, RenderCommand};
+use astro_math::*;
+
+/// Renders the provided layers into a row-major RGBA8 buffer of the specified size. Pixels
+/// nothing is drawn on are transparent black. Currently only `Clear` and `DrawRect` with a solid
+/// fill and a transform which is a pure translation are supported, other commands are skipped.
+pub fn render(layers: &[Layer], size: Size) -> Vec<u8> {
+    let (width, height) = (size.x.max(0.0) as usize, size.y.max(0.0) as usize);
+    let mut canvas = Canvas {
+        width,
+        height,
+        pixels: vec![0; width * height * 4],
+    };
+    for command in layers.iter().flat_map(|layer| layer.borrow_commands()) {
+        match command {
+            RenderCommand::Clear(FillMode::Solid(color)) => {
+                canvas.fill(Rect::new(0, size), *color, false)
+            }
+            RenderCommand::DrawRect {
+                transform,
+                top_left,
+                size,
+                fill: FillMode::Solid(color),
+            } if transform.is_translation() => {
+                let top_left = *top_left + (transform.ix, transform.iy);
+                canvas.fill(Rect::new(top_left, *size), *color, true)
+            }
+            _ => (),
+        }
+    }
+    canvas.pixels
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// Fills every pixel whose center is inside `rect`.
+    fn fill(&mut self, rect: Rect, color: Color, blend: bool) {
+        let bottom_right = rect.bottom_right();
+        let to_range = |start: f32, end: f32, limit: usize| {
+            // The first and one past the last pixel index whose center is in [start, end).
+            let first = (start - 0.5).ceil().max(0.0) as usize;
+            let last = ((end - 0.5).ceil().max(0.0) as usize).min(limit);
+            first..last
+        };
+        for y in to_range(rect.top_left.y, bottom_right.y, self.height) {
+            for x in to_range(rect.top_left.x, bottom_right.x, self.width) {
+                let index = (y * self.width + x) * 4;
+                let pixel = &mut self.pixels[index..index + 4];
+                if blend {
+                    blend_over(pixel, color);
+                } else {
+                    pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+                }
+            }
+        }
+    }
+}
+
+/// Draws `color` on top of `pixel`, taking the opacity of both into account.
+fn blend_over(pixel: &mut [u8], color: Color) {
+    let alpha = color.a as f32 / 255.0;
+    let dest_alpha = pixel[3] as f32 / 255.0;
+    let out_alpha = alpha + dest_alpha * (1.0 - alpha);
+    if out_alpha == 0.0 {
+        pixel.copy_from_slice(&[0, 0, 0, 0]);
+        return;
+    }
+    let channels = [color.r, color.g, color.b];
+    for (dest, &source) in pixel.iter_mut().zip(channels.iter()) {
+        let mixed = (source as f32 * alpha + *dest as f32 * dest_alpha * (1.0 - alpha)) / out_alpha;
+        *dest = mixed.round() as u8;
+    }
+    pixel[3] = (out_alpha * 255.0).round() as u8;
+}
@@ -0,0 +1,1389 @@
+#![cfg(test)]
+
+use crate::*;
+use std::cell::Cell;
+
+struct TestConfig;
+
+impl GuiConfig for TestConfig {
+    type Renderer = ();
+    type Measurer = ();
+}
+
+fn draw(widget: &impl RenderWidget<TestConfig>) -> Vec<Layer> {
+    GuiDrawer::new().draw::<TestConfig, _>(widget)
+}
+
+fn find_layer(layers: &[Layer], predicate: impl Fn(&RenderCommand) -> bool) -> usize {
+    layers
+        .iter()
+        .position(|layer| layer.borrow_commands().iter().any(&predicate))
+        .unwrap()
+}
+
+fn is_rect_at(command: &RenderCommand, x: f32, y: f32) -> bool {
+    match command {
+        RenderCommand::DrawRect { transform, .. } => transform.ix == x && transform.iy == y,
+        _ => false,
+    }
+}
+
+/// Draws a rect in a high layer group, optionally also drawing an overlay.
+struct LayeredRect {
+    overlay: bool,
+}
+
+impl RenderWidget<TestConfig> for LayeredRect {
+    fn layout(&mut self, _constraint: SizeConstraint, _measurer: &()) -> Size {
+        Size::new(100.0, 100.0)
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        if self.overlay {
+            drawer.draw_overlay::<TestConfig>(&SizedRect::new(100.0, 100.0), (5, 5));
+        }
+        drawer.begin_layer_group(100);
+        drawer.set_layer(100);
+        drawer.draw_rect(0, (100, 100));
+        drawer.end_layer_group();
+    }
+}
+
+#[test]
+fn overlay_above_siblings() {
+    let mut column = Column::new::<TestConfig>(vec![
+        LayeredRect { overlay: false },
+        LayeredRect { overlay: true },
+        LayeredRect { overlay: false },
+    ]);
+    column.layout(SizeConstraint::loose((800, 600)), &());
+    let layers = draw(&column);
+    let overlay = find_layer(&layers, |c| is_rect_at(c, 5.0, 105.0));
+    let first = find_layer(&layers, |c| is_rect_at(c, 0.0, 0.0));
+    let last = find_layer(&layers, |c| is_rect_at(c, 0.0, 200.0));
+    assert!(overlay > first);
+    assert!(overlay > last);
+    assert_eq!(overlay, layers.len() - 1);
+}
+
+#[test]
+fn tight_constraint() {
+    let constraint = SizeConstraint::tight((30, 40));
+    assert_eq!(constraint.min, Size::new(30.0, 40.0));
+    assert_eq!(constraint.max, Size::new(30.0, 40.0));
+}
+
+#[test]
+fn loose_constraint() {
+    let constraint = SizeConstraint::loose((30, 40));
+    assert_eq!(constraint.min, Size::new(0.0, 0.0));
+    assert_eq!(constraint.max, Size::new(30.0, 40.0));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "is larger than maximum")]
+fn inverted_constraint() {
+    SizeConstraint::new((50, 10), (40, 20));
+}
+
+#[test]
+fn stroke_records_dash_pattern() {
+    let mut context = DrawContext::new();
+    context.set_stroke_style(StrokeStyle {
+        width: 2.0,
+        dash: Some(vec![4.0, 2.0]),
+        dash_offset: 1.0,
+    });
+    context.stroke_rect(0, (10, 10));
+    let layers = context.finalize().flatten();
+    match &layers[0].borrow_commands()[0] {
+        RenderCommand::StrokeRect { stroke, .. } => {
+            assert_eq!(stroke.width, 2.0);
+            assert_eq!(stroke.dash, Some(vec![4.0, 2.0]));
+            assert_eq!(stroke.dash_offset, 1.0);
+        }
+        other => panic!("Expected a StrokeRect, got {:?}", other),
+    }
+}
+
+#[test]
+fn open_path_records_points() {
+    let mut context = DrawContext::new();
+    let points = vec![
+        Point::new(0.0, 0.0),
+        Point::new(10.0, 5.0),
+        Point::new(20.0, 0.0),
+    ];
+    context.draw_path(points.clone(), false);
+    let layers = context.finalize().flatten();
+    match &layers[0].borrow_commands()[0] {
+        RenderCommand::DrawPath {
+            points: recorded,
+            closed,
+            ..
+        } => {
+            assert_eq!(recorded, &points);
+            assert!(!closed);
+        }
+        other => panic!("Expected a DrawPath, got {:?}", other),
+    }
+}
+
+#[test]
+fn bezier_path_records_segments() {
+    let mut context = DrawContext::new();
+    context.path_move_to((0, 0));
+    context.path_cubic_to((0, 10), (10, 10), (10, 0));
+    context.path_close();
+    context.finish_path();
+    let layers = context.finalize().flatten();
+    match &layers[0].borrow_commands()[0] {
+        RenderCommand::DrawBezierPath { segments, .. } => assert_eq!(
+            segments,
+            &vec![
+                PathSegment::MoveTo(Point::new(0.0, 0.0)),
+                PathSegment::CubicTo(
+                    Point::new(0.0, 10.0),
+                    Point::new(10.0, 10.0),
+                    Point::new(10.0, 0.0)
+                ),
+                PathSegment::Close,
+            ]
+        ),
+        other => panic!("Expected a DrawBezierPath, got {:?}", other),
+    }
+}
+
+#[test]
+fn shadow_beneath_following_draw() {
+    let mut context = DrawContext::new();
+    context.draw_shadow(0, (10, 10), 4.0, (2, 3), Color::BLACK);
+    context.draw_rect(0, (10, 10));
+    let layers = context.finalize().flatten();
+    let shadow = find_layer(&layers, |c| match c {
+        RenderCommand::DrawShadow {
+            blur,
+            offset,
+            color,
+            ..
+        } => {
+            assert_eq!(*blur, 4.0);
+            assert_eq!(*offset, Vec2::new(2.0, 3.0));
+            assert_eq!((color.r, color.g, color.b, color.a), (0, 0, 0, 255));
+            true
+        }
+        _ => false,
+    });
+    let rect = find_layer(&layers, |c| is_rect_at(c, 0.0, 0.0));
+    assert!(shadow < rect);
+}
+
+/// Counts how many times it has been laid out.
+struct LayoutCounter {
+    id: u32,
+    layouts: u32,
+}
+
+impl LayoutCounter {
+    fn new(id: u32) -> Self {
+        Self { id, layouts: 0 }
+    }
+}
+
+impl RenderWidget<TestConfig> for LayoutCounter {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &()) -> Size {
+        self.layouts += 1;
+        constraint.min
+    }
+
+    fn draw(&self, _drawer: &mut DrawContext) {}
+}
+
+#[test]
+fn keyed_column_preserves_children() {
+    let mut column = Column::keyed::<TestConfig, _>(vec![
+        ("a", LayoutCounter::new(1)),
+        ("b", LayoutCounter::new(2)),
+    ]);
+    column.layout(SizeConstraint::loose((800, 600)), &());
+    column.rebuild(vec![
+        ("b", LayoutCounter::new(3)),
+        ("a", LayoutCounter::new(4)),
+        ("c", LayoutCounter::new(5)),
+    ]);
+    let children: Vec<_> = column
+        .column
+        .children
+        .iter()
+        .map(|(_, child)| (child.id, child.layouts))
+        .collect();
+    assert_eq!(children, vec![(2, 1), (1, 1), (5, 0)]);
+}
+
+#[test]
+fn composed_overlay_after_base() {
+    let mut base = DrawContext::new();
+    base.draw_rect(0, (10, 10));
+    base.set_layer(5);
+    base.draw_rect((20, 0), (10, 10));
+    let mut overlay = DrawContext::new();
+    overlay.set_layer(-5);
+    overlay.draw_rect((40, 0), (10, 10));
+    let layers = compose_layers(base.finalize().flatten(), overlay.finalize().flatten());
+    let commands: Vec<_> = layers
+        .iter()
+        .flat_map(|layer| layer.borrow_commands())
+        .collect();
+    assert_eq!(commands.len(), 3);
+    match commands[2] {
+        RenderCommand::DrawRect { top_left, .. } => assert_eq!(top_left.x, 40.0),
+        other => panic!("Expected a DrawRect, got {:?}", other),
+    }
+}
+
+fn count_commands(layers: &[Layer], predicate: impl Fn(&RenderCommand) -> bool) -> usize {
+    layers
+        .iter()
+        .flat_map(|layer| layer.borrow_commands())
+        .filter(|c| predicate(c))
+        .count()
+}
+
+fn is_draw_rect(command: &RenderCommand) -> bool {
+    matches!(command, RenderCommand::DrawRect { .. })
+}
+
+#[test]
+fn checkbox_fills_when_checked() {
+    let mut checkbox = Checkbox::new(false, 20.0);
+    let size = RenderWidget::<TestConfig>::layout(&mut checkbox, SizeConstraint::loose(100), &());
+    assert_eq!(size, Size::new(20.0, 20.0));
+    assert_eq!(count_commands(&draw(&checkbox), is_draw_rect), 0);
+    checkbox.checked = true;
+    let layers = draw(&checkbox);
+    assert_eq!(count_commands(&layers, is_draw_rect), 1);
+    assert_eq!(
+        count_commands(&layers, |c| matches!(c, RenderCommand::StrokeRect { .. })),
+        1
+    );
+}
+
+fn rect_sizes(layers: &[Layer]) -> Vec<Size> {
+    layers
+        .iter()
+        .flat_map(|layer| layer.borrow_commands())
+        .filter_map(|command| match command {
+            RenderCommand::DrawRect { size, .. } => Some(*size),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn progress_bar_half_full() {
+    let mut bar = ProgressBar::new(0.5, Color::BLACK, Color::WHITE, 10.0);
+    let size = RenderWidget::<TestConfig>::layout(&mut bar, SizeConstraint::loose((300, 50)), &());
+    assert_eq!(size, Size::new(300.0, 10.0));
+    let sizes = rect_sizes(&draw(&bar));
+    assert_eq!(sizes, vec![Size::new(300.0, 10.0), Size::new(150.0, 10.0)]);
+}
+
+#[test]
+fn progress_bar_respects_max_height() {
+    let mut bar = ProgressBar::new(0.5, Color::BLACK, Color::WHITE, 100.0);
+    let size = RenderWidget::<TestConfig>::layout(&mut bar, SizeConstraint::loose((300, 50)), &());
+    assert_eq!(size, Size::new(300.0, 50.0));
+    let sizes = rect_sizes(&draw(&bar));
+    assert_eq!(sizes, vec![Size::new(300.0, 50.0), Size::new(150.0, 50.0)]);
+}
+
+#[cfg(debug_assertions)]
+struct LeakyState;
+
+#[cfg(debug_assertions)]
+impl RenderWidget<TestConfig> for LeakyState {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &()) -> Size {
+        constraint.min
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.push_state();
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "push_state() was called more times than pop_state()")]
+fn unbalanced_state_detected() {
+    draw(&LeakyState);
+}
+
+#[test]
+fn translate_layer() {
+    let mut context = DrawContext::new();
+    context.translate((1, 2));
+    context.draw_rect((5, 5), (10, 10));
+    let layers = translate_layers(&context.finalize().flatten(), (10, 20));
+    match &layers[0].borrow_commands()[0] {
+        RenderCommand::DrawRect {
+            transform,
+            top_left,
+            ..
+        } => {
+            assert_eq!(*transform, Transform::translate(Vec2::new(11.0, 22.0)));
+            assert_eq!(*top_left, Point::new(5.0, 5.0));
+        }
+        other => panic!("Expected a DrawRect, got {:?}", other),
+    }
+}
+
+#[test]
+fn scale_inside_translate() {
+    let mut context = DrawContext::new();
+    context.translate((10, 0));
+    context.scale((2, 2));
+    context.draw_rect((0, 0), (1, 1));
+    let layers = context.finalize().flatten();
+    match &layers[0].borrow_commands()[0] {
+        RenderCommand::DrawRect { transform, .. } => {
+            // The scale applies first, so the translation itself is not scaled.
+            assert_eq!(*transform, Transform::new(2.0, 0.0, 10.0, 0.0, 2.0, 0.0));
+            assert_eq!(Point::new(1.0, 1.0) * *transform, Point::new(12.0, 2.0));
+        }
+        other => panic!("Expected a DrawRect, got {:?}", other),
+    }
+}
+
+#[test]
+fn collected_column_matches_new() {
+    let mut from_vec = Column::new::<TestConfig>(vec![DebugRect::new(), DebugRect::new()]);
+    let mut collected: Column<_> = (0..2).map(|_| DebugRect::new()).collect();
+    let mut pushed = Column::new::<TestConfig>(vec![DebugRect::new()]);
+    pushed.push(DebugRect::new());
+    let constraint = SizeConstraint::loose(Size::new(800.0, 600.0));
+    let expected = RenderWidget::<TestConfig>::layout(&mut from_vec, constraint, &());
+    for column in [&mut collected, &mut pushed].iter_mut() {
+        let size = RenderWidget::<TestConfig>::layout(&mut **column, constraint, &());
+        assert_eq!(size, expected);
+    }
+    let layers = draw(&collected);
+    assert_eq!(count_commands(&layers, |c| is_rect_at(c, 0.0, 100.0)), 1);
+}
+
+#[test]
+fn debug_rect_respects_constraint() {
+    let constraint = SizeConstraint::loose(Size::new(50.0, 50.0));
+    let mut rect = DebugRect::new();
+    let size = RenderWidget::<TestConfig>::layout(&mut rect, constraint, &());
+    assert_eq!(size, Size::new(50.0, 50.0));
+    assert_eq!(rect_sizes(&draw(&rect)), vec![Size::new(50.0, 50.0)]);
+}
+
+#[test]
+fn example_scene_stats() {
+    let list =
+        Column::new::<TestConfig>(vec![DebugRect::new(), DebugRect::new(), DebugRect::new()]);
+    let mut root = AlignBox::new::<TestConfig>(Center, Middle, list);
+    let drawer = GuiDrawer::new();
+    drawer.layout::<TestConfig, _>(&mut root, &());
+    let (layers, stats) = drawer.draw_with_stats::<TestConfig, _>(&root);
+    assert_eq!(stats.count("DrawRect"), 3);
+    assert_eq!(stats.total_commands, 3);
+    assert_eq!(stats.num_layers, layers.len());
+}
+
+#[test]
+fn unfilled_rect_records_no_fill() {
+    let mut context = DrawContext::new();
+    context.set_fill_mode(FillMode::None);
+    context.draw_rect(0, (10, 10));
+    context.draw_path(vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)], false);
+    let layers = context.finalize().flatten();
+    match layers[0].borrow_commands() {
+        [RenderCommand::DrawRect {
+            fill: FillMode::None,
+            ..
+        }, RenderCommand::DrawPath { fill: None, .. }] => (),
+        other => panic!("Expected unfilled commands, got {:?}", other),
+    }
+}
+
+#[test]
+fn recycled_buffers_are_reused() {
+    let widget = LayeredRect { overlay: true };
+    let drawer = GuiDrawer::new();
+    let mut pool = BufferPool::new();
+    let first = drawer.draw_with_buffers::<TestConfig, _>(&widget, &mut pool);
+    let first_buffers: Vec<_> = first
+        .iter()
+        .map(|layer| layer.borrow_commands().as_ptr())
+        .collect();
+    pool.recycle(first);
+    assert_eq!(pool.len(), first_buffers.len());
+    let second = drawer.draw_with_buffers::<TestConfig, _>(&widget, &mut pool);
+    assert_eq!(second.len(), first_buffers.len());
+    for layer in &second {
+        assert!(first_buffers.contains(&layer.borrow_commands().as_ptr()));
+    }
+    assert!(pool.is_empty());
+}
+
+#[test]
+fn empty_layers_are_pruned() {
+    let mut context = DrawContext::new();
+    context.begin_layer_group(3);
+    context.set_layer(2);
+    context.end_layer_group();
+    context.draw_rect(0, (10, 10));
+    context
+        .top_layer_group()
+        .borrow_layer_mut(5, &mut BufferPool::new());
+    let layers = context.finalize().flatten();
+    assert_eq!(layers.len(), 1);
+    assert!(layers.iter().all(|layer| !layer.is_empty()));
+}
+
+#[test]
+fn builder_wraps_in_order() {
+    let mut widget: AlignBox<Padding<SizedRect>> = SizedRect::new(100.0, 100.0)
+        .padded(8.0)
+        .aligned(Center, Center);
+    assert_eq!(widget.child.amount, 8.0);
+    let size =
+        RenderWidget::<TestConfig>::layout(&mut widget, SizeConstraint::tight((216, 216)), &());
+    assert_eq!(size, Size::new(216.0, 216.0));
+    let layers = draw(&widget);
+    // The aligned box puts the 116x116 padded box at 50, 50, which puts the rect at 58, 58.
+    assert_eq!(count_commands(&layers, |c| is_rect_at(c, 58.0, 58.0)), 1);
+}
+
+#[test]
+fn background_fills_child_size() {
+    let mut widget = SizedRect::new(100.0, 100.0)
+        .padded(5.0)
+        .background(Color::BLACK);
+    RenderWidget::<TestConfig>::layout(&mut widget, SizeConstraint::loose((800, 600)), &());
+    let layers = draw(&widget);
+    assert_eq!(
+        rect_sizes(&layers),
+        vec![Size::new(110.0, 110.0), Size::new(100.0, 100.0)]
+    );
+}
+
+#[test]
+fn expand_fills_constraint() {
+    let mut widget = Expand::new::<TestConfig>(DebugRect::new());
+    let constraint = SizeConstraint::loose((800, 600));
+    let size = RenderWidget::<TestConfig>::layout(&mut widget, constraint, &());
+    assert_eq!(size, Size::new(800.0, 600.0));
+}
+
+struct ThemedConfig;
+
+impl GuiConfig for ThemedConfig {
+    type Renderer = ();
+    type Measurer = ();
+
+    fn theme() -> Theme {
+        Theme {
+            background: Color::MAGENTA,
+            ..Default::default()
+        }
+    }
+}
+
+#[test]
+fn container_uses_theme_background() {
+    let mut widget = Container::new::<ThemedConfig>(None, DebugRect::new());
+    RenderWidget::<ThemedConfig>::layout(&mut widget, SizeConstraint::loose((800, 600)), &());
+    let layers = GuiDrawer::new().draw::<ThemedConfig, _>(&widget);
+    match &layers[0].borrow_commands()[0] {
+        RenderCommand::DrawRect {
+            fill: FillMode::Solid(color),
+            ..
+        } => assert_eq!(color.r, Color::MAGENTA.r),
+        other => panic!("Expected a DrawRect, got {:?}", other),
+    }
+}
+
+#[test]
+fn scene_bounds_covers_all_rects() {
+    let mut context = DrawContext::new();
+    assert_eq!(scene_bounds(&[]), None);
+    context.draw_rect((10, 10), (10, 10));
+    context.translate((50, 0));
+    context.draw_rect((0, 40), (5, 5));
+    let layers = context.finalize().flatten();
+    assert_eq!(scene_bounds(&layers), Some(Rect::new((10, 10), (45, 35))));
+}
+
+#[test]
+fn with_fill_restores_only_fill() {
+    let mut context = DrawContext::new();
+    context.fill_solid_color(Color::BLACK);
+    context.with_fill(FillMode::Solid(Color::MAGENTA), |context| {
+        context.translate((10, 0));
+        context.draw_rect(0, 1);
+    });
+    context.draw_rect(0, 1);
+    let layers = context.finalize().flatten();
+    match layers[0].borrow_commands() {
+        [RenderCommand::DrawRect {
+            fill: FillMode::Solid(inner),
+            ..
+        }, RenderCommand::DrawRect {
+            fill: FillMode::Solid(outer),
+            ..
+        }] => {
+            assert_eq!(inner.r, Color::MAGENTA.r);
+            assert_eq!(outer.r, Color::BLACK.r);
+        }
+        other => panic!("Expected two rects, got {:?}", other),
+    }
+    assert_eq!(count_commands(&layers, |c| is_rect_at(c, 10.0, 0.0)), 2);
+}
+
+/// Makes a group with one layer per entry of `layers`, each containing a rect whose x position
+/// identifies it.
+fn marked_group(layers: &[(i8, f32)]) -> LayerGroup {
+    let mut group = LayerGroup::new();
+    let mut pool = BufferPool::new();
+    for &(height, id) in layers {
+        group
+            .borrow_layer_mut(height, &mut pool)
+            .command_buffer
+            .push(RenderCommand::DrawRect {
+                transform: Transform::identity(),
+                top_left: Point::new(id, 0.0),
+                size: Size::new(1.0, 1.0),
+                fill: FillMode::None,
+            });
+    }
+    group
+}
+
+fn layer_ids(layers: &[Layer]) -> Vec<f32> {
+    layers
+        .iter()
+        .map(|layer| match &layer.borrow_commands()[0] {
+            RenderCommand::DrawRect { top_left, .. } => top_left.x,
+            other => panic!("Expected a DrawRect, got {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn flatten_puts_layer_before_subgroups_at_same_height() {
+    let mut root = marked_group(&[(0, 0.0), (1, 1.0)]);
+    root.add_subgroup(0, marked_group(&[(-5, 10.0), (5, 11.0)]));
+    root.add_subgroup(0, marked_group(&[(0, 20.0)]));
+    assert_eq!(layer_ids(&root.flatten()), vec![0.0, 10.0, 11.0, 20.0, 1.0]);
+}
+
+#[test]
+fn flatten_preserves_height_order() {
+    let mut inner = marked_group(&[(2, 100.0), (-2, 101.0)]);
+    inner.add_subgroup(0, marked_group(&[(i8::MIN, 200.0)]));
+    let mut middle = marked_group(&[(1, 10.0)]);
+    middle.add_subgroup(3, inner);
+    middle.add_subgroup(-1, marked_group(&[(0, 11.0)]));
+    let mut root = marked_group(&[(-3, 0.0), (4, 1.0), (i8::MAX, 2.0)]);
+    root.add_subgroup(4, middle);
+    assert_eq!(
+        layer_ids(&root.flatten()),
+        vec![0.0, 1.0, 11.0, 10.0, 101.0, 200.0, 100.0, 2.0]
+    );
+}
+
+#[test]
+fn split_axis_aligned_rects() {
+    let mut context = DrawContext::new();
+    context.translate((10, 20));
+    context.draw_rect((1, 2), (5, 5));
+    context.set_transform(Transform::new(0.0, -1.0, 0.0, 1.0, 0.0, 0.0));
+    context.draw_rect(0, (5, 5));
+    let layers = context.finalize().flatten();
+    let (axis_aligned, others) = layers[0].split_axis_aligned();
+    assert_eq!(axis_aligned.len(), 1);
+    assert_eq!(axis_aligned[0].top_left, Point::new(11.0, 22.0));
+    assert_eq!(others.len(), 1);
+    assert!(is_draw_rect(&others[0]));
+}
+
+struct DrawCounter(Cell<usize>);
+
+impl RenderWidget<TestConfig> for DrawCounter {
+    fn layout(&mut self, _constraint: SizeConstraint, _measurer: &()) -> Size {
+        Size::new(10.0, 10.0)
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        self.0.set(self.0.get() + 1);
+        drawer.translate((1, 2));
+        drawer.draw_rect(0, (10, 10));
+    }
+}
+
+#[test]
+fn cached_draw_reuses_output() {
+    let mut widget = CachedDraw::new(DrawCounter(Cell::new(0)));
+    RenderWidget::<TestConfig>::layout(&mut widget, SizeConstraint::loose((800, 600)), &());
+    let drawer = GuiDrawer::new();
+    let first = drawer.draw::<TestConfig, _>(&widget);
+    let second = drawer.draw::<TestConfig, _>(&widget);
+    assert_eq!(widget.child.0.get(), 1);
+    assert_eq!(count_commands(&first, |c| is_rect_at(c, 1.0, 2.0)), 1);
+    assert_eq!(count_commands(&second, |c| is_rect_at(c, 1.0, 2.0)), 1);
+    let mut context = DrawContext::new();
+    context.draw_child(&widget, (10, 10), (10, 10));
+    let moved = context.finalize().flatten();
+    assert_eq!(count_commands(&moved, |c| is_rect_at(c, 11.0, 12.0)), 1);
+    widget.invalidate();
+    drawer.draw::<TestConfig, _>(&widget);
+    assert_eq!(widget.child.0.get(), 2);
+}
+
+#[test]
+fn with_alpha_keeps_rgb() {
+    let color = Color::RED.with_alpha(128);
+    assert_eq!((color.r, color.g, color.b, color.a), (255, 0, 0, 128));
+    assert_eq!(Color::WHITE.with_alpha_f(0.5).a, 128);
+    assert_eq!(Color::WHITE.with_alpha_f(2.0).a, 255);
+}
+
+#[test]
+fn measured_records_child_size() {
+    let measured = Measured::new::<TestConfig>(DebugRect::new());
+    assert_eq!(measured.size(), None);
+    let mut root = AlignBox::new::<TestConfig>(Center, Middle, measured);
+    GuiDrawer::new().layout::<TestConfig, _>(&mut root, &());
+    assert_eq!(root.child.size(), Some(Size::new(100.0, 100.0)));
+}
+
+#[test]
+fn popup_flips_when_no_room() {
+    let anchor = Rect::new((100, 560), (50, 20));
+    let position = position_popup(
+        anchor,
+        Size::new(80.0, 40.0),
+        (800, 600).into(),
+        Side::Bottom,
+    );
+    assert_eq!(position, Point::new(100.0, 520.0));
+    let position = position_popup(
+        anchor,
+        Size::new(80.0, 10.0),
+        (800, 600).into(),
+        Side::Bottom,
+    );
+    assert_eq!(position, Point::new(100.0, 580.0));
+}
+
+#[test]
+fn popup_clamped_to_screen() {
+    let anchor = Rect::new((760, 100), (20, 20));
+    let position = position_popup(
+        anchor,
+        Size::new(100.0, 40.0),
+        (800, 600).into(),
+        Side::Bottom,
+    );
+    assert_eq!(position, Point::new(700.0, 120.0));
+    let position = position_popup(
+        anchor,
+        Size::new(100.0, 700.0),
+        (800, 600).into(),
+        Side::Top,
+    );
+    assert_eq!(position, Point::new(700.0, 0.0));
+}
+
+#[test]
+fn equal_column_divides_height() {
+    let children = (0..4)
+        .map(|_| Expand::new::<TestConfig>(DebugRect::new()))
+        .collect();
+    let mut column = EqualColumn::new::<TestConfig>(children);
+    let size =
+        RenderWidget::<TestConfig>::layout(&mut column, SizeConstraint::loose((200, 400)), &());
+    assert_eq!(size, Size::new(200.0, 400.0));
+    let layers = draw(&column);
+    assert_eq!(rect_sizes(&layers), vec![Size::new(200.0, 100.0); 4]);
+    for index in 0..4 {
+        let y = index as f32 * 100.0;
+        assert_eq!(count_commands(&layers, |c| is_rect_at(c, 0.0, y)), 1);
+    }
+}
+
+#[test]
+fn dedup_fills_shares_palette_entries() {
+    let mut context = DrawContext::new();
+    context.fill_solid_color(Color::RED);
+    context.draw_rect(0, 1);
+    context.draw_rect(1, 1);
+    context.translate((5, 5));
+    context.draw_rect(2, 1);
+    let (palette, layers) = dedup_fills(context.finalize().flatten());
+    assert_eq!(palette, vec![FillMode::Solid(Color::RED)]);
+    for command in layers[0].borrow_commands() {
+        match command {
+            RenderCommand::DrawRect { fill, .. } => assert_eq!(*fill, 0),
+            other => panic!("Expected a DrawRect, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn none_child_is_empty() {
+    let mut column =
+        Column::new::<TestConfig>(vec![Some(DebugRect::new()), None, Some(DebugRect::new())]);
+    let size =
+        RenderWidget::<TestConfig>::layout(&mut column, SizeConstraint::loose((800, 600)), &());
+    assert_eq!(size, Size::new(100.0, 200.0));
+    let layers = draw(&column);
+    assert_eq!(count_commands(&layers, is_draw_rect), 2);
+    assert_eq!(count_commands(&layers, |c| is_rect_at(c, 0.0, 100.0)), 1);
+}
+
+/// Would like to be a particular size, but accepts whatever the constraint forces on it.
+struct SizedRect {
+    preferred: Size,
+    size: Size,
+}
+
+impl SizedRect {
+    fn new(width: f32, height: f32) -> Self {
+        let preferred = Size::new(width, height);
+        Self {
+            preferred,
+            size: preferred,
+        }
+    }
+}
+
+impl RenderWidget<TestConfig> for SizedRect {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &()) -> Size {
+        self.size = constraint.constrain(self.preferred);
+        self.size
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.draw_rect(0, self.size);
+    }
+}
+
+#[test]
+fn row_fill_pushes_to_edge() {
+    let children = vec![
+        SizedRect::new(50.0, 10.0),
+        SizedRect::new(20.0, 10.0),
+        SizedRect::new(30.0, 20.0),
+    ];
+    let mut row = Row::with_fill::<TestConfig>(children, 1);
+    let size = RenderWidget::<TestConfig>::layout(&mut row, SizeConstraint::loose((400, 100)), &());
+    assert_eq!(size, Size::new(400.0, 20.0));
+    let layers = draw(&row);
+    assert_eq!(
+        rect_sizes(&layers),
+        vec![
+            Size::new(50.0, 10.0),
+            Size::new(320.0, 10.0),
+            Size::new(30.0, 20.0)
+        ]
+    );
+    assert_eq!(count_commands(&layers, |c| is_rect_at(c, 370.0, 0.0)), 1);
+}
+
+#[test]
+fn identical_scenes_are_equal() {
+    let scene = |offset: f32| {
+        let mut column = Column::new::<TestConfig>(vec![DebugRect::new(), DebugRect::new()]);
+        RenderWidget::<TestConfig>::layout(&mut column, SizeConstraint::loose((800, 600)), &());
+        let mut context = DrawContext::new();
+        context.draw_child::<TestConfig>(&column, (offset, 0.0), (100, 200));
+        context.finalize().flatten()
+    };
+    assert!(layers_eq(&scene(0.0), &scene(0.0)));
+    assert!(!layers_eq(&scene(0.0), &scene(1.0)));
+}
+
+#[test]
+fn debug_outlines_wrap_children() {
+    let rects = (0..3).map(|_| SizedRect::new(100.0, 100.0)).collect();
+    let mut root = Column::new::<TestConfig>(rects)
+        .padded(5.0)
+        .aligned(Center, Middle);
+    let drawer = GuiDrawer {
+        debug_outlines: true,
+    };
+    drawer.layout::<TestConfig, _>(&mut root, &());
+    let layers = drawer.draw::<TestConfig, _>(&root);
+    let is_stroke = |c: &RenderCommand| matches!(c, RenderCommand::StrokeRect { .. });
+    // One for each rect, one for the column and one for the padding.
+    assert_eq!(count_commands(&layers, is_stroke), 5);
+    let outline_sizes: Vec<_> = layers[0]
+        .borrow_commands()
+        .iter()
+        .filter_map(|c| match c {
+            RenderCommand::StrokeRect { size, .. } => Some(*size),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(outline_sizes[3], Size::new(100.0, 300.0));
+    assert_eq!(outline_sizes[4], Size::new(110.0, 310.0));
+    assert_eq!(count_commands(&draw(&root), is_stroke), 0);
+}
+
+#[test]
+#[should_panic(expected = "end_layer_group() called without a matching begin_layer_group()")]
+fn unmatched_end_layer_group() {
+    let mut context = DrawContext::new();
+    context.begin_layer_group(1);
+    context.end_layer_group();
+    context.end_layer_group();
+}
+
+#[test]
+#[should_panic(expected = "pop_state() called without a matching push_state()")]
+fn unmatched_pop_state() {
+    let mut context = DrawContext::new();
+    context.push_state();
+    context.pop_state();
+    context.pop_state();
+}
+
+#[test]
+fn software_render_red_rect() {
+    let mut context = DrawContext::new();
+    context.fill_solid_color(Color::BLACK);
+    context.clear();
+    context.fill_solid_color(Color::RED);
+    context.translate((1, 1));
+    context.draw_rect((1, 0), (2, 3));
+    let pixels = software::render(&context.finalize().flatten(), Size::new(5.0, 5.0));
+    assert_eq!(pixels.len(), 5 * 5 * 4);
+    let pixel = |x: usize, y: usize| &pixels[(y * 5 + x) * 4..][..4];
+    for y in 0..5 {
+        for x in 0..5 {
+            if (2..4).contains(&x) && (1..4).contains(&y) {
+                assert_eq!(pixel(x, y), &[255, 0, 0, 255], "at {}, {}", x, y);
+            } else {
+                assert_eq!(pixel(x, y), &[0, 0, 0, 255], "at {}, {}", x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn image_fit_contain_letterboxes() {
+    let dest = Rect::new((10, 20), (100, 200));
+    let (source, target) = ImageFit::Contain.compute_rects(Size::new(400.0, 100.0), dest);
+    assert_eq!(source, Rect::new(0, (400, 100)));
+    assert_eq!(target, Rect::new((10, 107.5), (100, 25)));
+}
+
+#[test]
+fn image_fit_cover_crops() {
+    let dest = Rect::new((10, 20), (100, 200));
+    let (source, target) = ImageFit::Cover.compute_rects(Size::new(400.0, 100.0), dest);
+    assert_eq!(source, Rect::new((175, 0), (50, 100)));
+    assert_eq!(target, dest);
+}
+
+#[test]
+fn image_fit_fill_and_stretch() {
+    let dest = Rect::new(0, (100, 200));
+    let image = Size::new(400.0, 100.0);
+    let (source, target) = ImageFit::Fill.compute_rects(image, dest);
+    assert_eq!(source, Rect::new((150, 0), (100, 100)));
+    assert_eq!(target, Rect::new((0, 50), (100, 100)));
+    let (source, target) = ImageFit::Stretch.compute_rects(image, dest);
+    assert_eq!((source, target), (Rect::new(0, image), dest));
+}
+
+#[test]
+fn clip_box_brackets_child() {
+    let mut widget = ClipBox::new::<TestConfig>(SizedRect::new(50.0, 500.0));
+    RenderWidget::<TestConfig>::layout(&mut widget, SizeConstraint::loose((800, 300)), &());
+    let layers = draw(&widget);
+    match layers[0].borrow_commands() {
+        [RenderCommand::PushClip { size, .. }, RenderCommand::DrawRect { .. }, RenderCommand::PopClip] =>
+        {
+            assert_eq!(*size, Size::new(50.0, 300.0))
+        }
+        other => panic!("Expected a clipped rect, got {:?}", other),
+    }
+}
+
+#[test]
+fn color_formats_as_hex() {
+    assert_eq!(Color::RED.to_string(), "#ff0000ff");
+    assert_eq!(format!("{:?}", Color::from((1, 2, 3, 4))), "#01020304");
+}
+
+#[test]
+fn constraint_predicates() {
+    let tight = SizeConstraint::tight((10, 20));
+    assert!(tight.is_tight() && tight.is_bounded());
+    let loose = SizeConstraint::loose((10, 20));
+    assert!(!loose.is_tight() && loose.is_bounded());
+    let unbounded = loose.with_max_height(f32::INFINITY);
+    assert!(!unbounded.is_tight() && !unbounded.is_bounded());
+}
+
+#[derive(Default)]
+struct MountCounter {
+    mounts: u32,
+    unmounts: u32,
+    mounted_at_layout: Option<bool>,
+}
+
+impl RenderWidget<TestConfig> for MountCounter {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &()) -> Size {
+        self.mounted_at_layout = Some(self.mounts > self.unmounts);
+        constraint.min
+    }
+
+    fn draw(&self, _drawer: &mut DrawContext) {}
+}
+
+impl RootWidget<TestConfig> for MountCounter {
+    fn mount(&mut self) {
+        self.mounts += 1;
+    }
+
+    fn unmount(&mut self) {
+        self.unmounts += 1;
+    }
+}
+
+#[test]
+fn root_is_mounted_once_before_layout() {
+    let mut root = Root::new::<TestConfig>(MountCounter::default());
+    GuiDrawer::new().layout::<TestConfig, _>(&mut root, &());
+    assert_eq!(root.widget().mounted_at_layout, Some(true));
+    // A different drawer must not mount the widget a second time.
+    GuiDrawer::new().layout::<TestConfig, _>(&mut root, &());
+    assert_eq!(root.widget().mounts, 1);
+    root.unmount::<TestConfig>();
+    root.unmount::<TestConfig>();
+    assert_eq!(root.widget().unmounts, 1);
+    assert!(!root.is_mounted());
+}
+
+#[test]
+fn each_root_is_mounted() {
+    let drawer = GuiDrawer::new();
+    let mut first = Root::new::<TestConfig>(MountCounter::default());
+    let mut second = Root::new::<TestConfig>(MountCounter::default());
+    drawer.layout::<TestConfig, _>(&mut first, &());
+    drawer.layout::<TestConfig, _>(&mut second, &());
+    assert_eq!(first.widget().mounts, 1);
+    assert_eq!(second.widget().mounts, 1);
+}
+
+#[test]
+fn zero_size_rect_is_skipped() {
+    let mut context = DrawContext::new();
+    context.draw_rect(0, (0, 10));
+    assert!(context.finalize().flatten().is_empty());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "negative size")]
+fn negative_size_rect() {
+    DrawContext::new().draw_rect(0, (-1, 10));
+}
+
+#[test]
+fn consecutive_clears_are_merged() {
+    let mut context = DrawContext::new();
+    context.with_fill(FillMode::Solid(Color::RED), |context| context.clear());
+    context.with_fill(FillMode::Solid(Color::WHITE), |context| context.clear());
+    context.draw_rect(0, (10, 10));
+    context.clear();
+    let layers = context.finalize().flatten();
+    match layers[0].borrow_commands() {
+        [RenderCommand::Clear(FillMode::Solid(Color::WHITE)), RenderCommand::DrawRect { .. }, RenderCommand::Clear(..)] =>
+            {}
+        other => panic!("Expected a single clear before the rect, got {:?}", other),
+    }
+}
+
+#[test]
+fn named_layers_are_ordered() {
+    let mut context = DrawContext::new();
+    context.set_layer(LayerId::OVERLAY);
+    context.fill_solid_color(Color::RED);
+    context.draw_rect(0, (10, 10));
+    context.set_layer(LayerId::CONTENT);
+    context.fill_solid_color(Color::WHITE);
+    context.draw_rect(0, (10, 10));
+    let layers = context.finalize().flatten();
+    let fills: Vec<_> = layers
+        .iter()
+        .map(|layer| match &layer.borrow_commands()[0] {
+            RenderCommand::DrawRect { fill, .. } => fill.clone(),
+            other => panic!("Expected a DrawRect, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        fills,
+        vec![FillMode::Solid(Color::WHITE), FillMode::Solid(Color::RED)]
+    );
+}
+
+#[test]
+fn clipped_column_overflow() {
+    let children = (0..3).map(|_| SizedRect::new(100.0, 300.0)).collect();
+    let mut column = Column::new::<TestConfig>(children).with_overflow(Overflow::Clip);
+    RenderWidget::<TestConfig>::layout(&mut column, SizeConstraint::loose((800, 600)), &());
+    let layers = draw(&column);
+    let commands = layers[0].borrow_commands();
+    match (commands.first(), commands.last()) {
+        (Some(RenderCommand::PushClip { size, .. }), Some(RenderCommand::PopClip)) => {
+            assert_eq!(*size, Size::new(100.0, 600.0))
+        }
+        other => panic!("Expected the column to be clipped, got {:?}", other),
+    }
+    assert_eq!(count_commands(&layers, is_draw_rect), 3);
+}
+
+struct FixedMeasurer;
+
+impl Measurer for FixedMeasurer {
+    fn measure_text(&self, _text: &str, size: f32) -> Size {
+        Size::new(40.0, size)
+    }
+}
+
+struct MeasuringConfig;
+
+impl GuiConfig for MeasuringConfig {
+    type Renderer = ();
+    type Measurer = FixedMeasurer;
+}
+
+struct Label(&'static str);
+
+impl RenderWidget<MeasuringConfig> for Label {
+    fn layout(&mut self, constraint: SizeConstraint, measurer: &FixedMeasurer) -> Size {
+        constraint.constrain(measurer.measure_text(self.0, 12.0))
+    }
+
+    fn draw(&self, _drawer: &mut DrawContext) {}
+}
+
+#[test]
+fn layout_uses_measurer() {
+    let mut widget = Padding::new::<MeasuringConfig>(4.0, Label("hello"));
+    let constraint = SizeConstraint::loose((800, 600));
+    let size = RenderWidget::<MeasuringConfig>::layout(&mut widget, constraint, &FixedMeasurer);
+    assert_eq!(size, Size::new(48.0, 20.0));
+}
+
+#[test]
+fn debug_tree_lists_commands() {
+    let mut context = DrawContext::new();
+    context.fill_solid_color(Color::MAGENTA);
+    context.translate((5, 0));
+    context.draw_rect(0, (100, 50));
+    context.push_clip(0, (10, 10));
+    context.pop_clip();
+    let tree = debug_tree(&context.finalize().flatten());
+    assert_eq!(
+        tree,
+        "Layer 0:\n  DrawRect @(5,0) 100x50 fill=#ff00ffff\n  PushClip @(5,0) 10x10\n  PopClip\n"
+    );
+}
+
+#[test]
+fn sub_scene_is_offset() {
+    let mut inner = DrawContext::new();
+    inner.draw_rect((1, 2), (10, 10));
+    let layers = inner.finalize().flatten();
+    let mut column = Column::new::<TestConfig>(vec![
+        SubScene::new((20, 50), Vec::new()),
+        SubScene::new((20, 20), layers),
+    ]);
+    RenderWidget::<TestConfig>::layout(&mut column, SizeConstraint::loose((800, 600)), &());
+    let layers = draw(&column);
+    assert_eq!(count_commands(&layers, is_draw_rect), 1);
+    find_layer(&layers, |c| is_rect_at(c, 0.0, 50.0));
+    assert_eq!(scene_bounds(&layers), Some(Rect::new((1, 52), (10, 10))));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Layer height 127 is reserved for astro_gui")]
+fn reserved_layer_rejected() {
+    DrawContext::new().begin_layer_group(i8::MAX);
+}
+
+#[test]
+fn rotated_rect_contains_point() {
+    let rect = RenderCommand::DrawRect {
+        transform: Transform::from_decomposed(DecomposedTransform {
+            translation: Vec2::new(100.0, 100.0),
+            rotation: std::f32::consts::FRAC_PI_4,
+            scale: Vec2::new(1.0, 1.0),
+        }),
+        top_left: 0.into(),
+        size: Size::new(10.0, 10.0),
+        fill: FillMode::None,
+    };
+    // The rect is now a diamond hanging down from (100, 100).
+    assert!(rect.contains_point((100.0, 107.0)));
+    assert!(!rect.contains_point((107.0, 100.0)));
+    assert!(!rect.contains_point((100.0, 99.0)));
+}
+
+#[test]
+fn zebra_column_alternates_backgrounds() {
+    let children = (0..3).map(|_| SizedRect::new(100.0, 20.0)).collect();
+    let mut column = Column::zebra::<TestConfig>(children, Color::WHITE, Color::BLACK);
+    RenderWidget::<TestConfig>::layout(&mut column, SizeConstraint::loose((800, 600)), &());
+    let layers = draw(&column);
+    let mut backgrounds: Vec<_> = layers[0]
+        .borrow_commands()
+        .iter()
+        .filter_map(|command| match command {
+            RenderCommand::DrawRect {
+                transform, fill, ..
+            } => Some((transform.iy, fill.clone())),
+            _ => None,
+        })
+        .collect();
+    backgrounds.dedup();
+    assert_eq!(
+        backgrounds,
+        vec![
+            (0.0, FillMode::Solid(Color::WHITE)),
+            (20.0, FillMode::Solid(Color::BLACK)),
+            (40.0, FillMode::Solid(Color::WHITE)),
+        ]
+    );
+}
+
+/// A rect with a baseline a fixed distance from its top.
+struct BaselineRect {
+    size: Size,
+    baseline: f32,
+}
+
+impl RenderWidget<TestConfig> for BaselineRect {
+    fn layout(&mut self, constraint: SizeConstraint, _measurer: &()) -> Size {
+        constraint.constrain(self.size)
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        drawer.draw_rect(0, self.size);
+    }
+
+    fn baseline(&self) -> Option<f32> {
+        Some(self.baseline)
+    }
+}
+
+#[test]
+fn row_aligns_baselines() {
+    let children = vec![
+        BaselineRect {
+            size: Size::new(10.0, 20.0),
+            baseline: 15.0,
+        },
+        BaselineRect {
+            size: Size::new(10.0, 40.0),
+            baseline: 30.0,
+        },
+    ];
+    let mut row =
+        Row::new::<TestConfig>(children).with_cross_axis_alignment(CrossAxisAlignment::Baseline);
+    let size = RenderWidget::<TestConfig>::layout(&mut row, SizeConstraint::loose((800, 600)), &());
+    assert_eq!(size, Size::new(20.0, 40.0));
+    assert_eq!(RenderWidget::<TestConfig>::baseline(&row), Some(30.0));
+    let layers = draw(&row);
+    find_layer(&layers, |c| is_rect_at(c, 0.0, 15.0));
+    find_layer(&layers, |c| is_rect_at(c, 10.0, 0.0));
+}
+
+#[test]
+fn exact_constraint() {
+    let constraint = SizeConstraint::exactly((100, 100));
+    assert_eq!(constraint.min, constraint.max);
+    assert!(constraint.is_tight());
+    let constraint = SizeConstraint::from_sizes((10, 20), (30, 40));
+    assert_eq!(
+        (constraint.min, constraint.max),
+        (Size::new(10.0, 20.0), Size::new(30.0, 40.0))
+    );
+}
+
+#[derive(Default)]
+struct RecordingRenderer {
+    calls: Vec<String>,
+}
+
+impl Renderer for RecordingRenderer {
+    fn clear(&mut self, fill: &FillMode) {
+        self.calls.push(format!("clear {:?}", fill));
+    }
+
+    fn fill_rect(&mut self, transform: Transform, rect: Rect, _fill: &FillMode) {
+        let origin = rect.top_left * transform;
+        self.calls
+            .push(format!("fill_rect {} {}", origin.x, origin.y));
+    }
+}
+
+struct RecordingConfig;
+
+impl GuiConfig for RecordingConfig {
+    type Renderer = RecordingRenderer;
+    type Measurer = ();
+}
+
+#[test]
+fn example_scene_renders_three_rects() {
+    let list =
+        Column::new::<RecordingConfig>(vec![DebugRect::new(), DebugRect::new(), DebugRect::new()]);
+    let mut root = AlignBox::new::<RecordingConfig>(Center, Middle, list);
+    let drawer = GuiDrawer::new();
+    drawer.layout::<RecordingConfig, _>(&mut root, &());
+    let mut renderer = RecordingRenderer::default();
+    drawer.render::<RecordingConfig, _>(&root, &mut renderer);
+    assert_eq!(
+        renderer.calls,
+        vec![
+            "fill_rect 350 150",
+            "fill_rect 350 250",
+            "fill_rect 350 350"
+        ]
+    );
+}
+
+#[test]
+fn rollback_discards_drawing() {
+    let mut context = DrawContext::new();
+    let checkpoint = context.checkpoint();
+    context.translate((10, 10));
+    context.draw_rect(0, (10, 10));
+    context.set_layer(3);
+    context.draw_rect(0, (10, 10));
+    context.draw_overlay::<TestConfig>(&DebugRect::new(), 0);
+    context.rollback(checkpoint);
+    context.draw_rect(0, (5, 5));
+    let layers = context.finalize().flatten();
+    assert_eq!(layers.len(), 1);
+    match layers[0].borrow_commands() {
+        [RenderCommand::DrawRect { transform, .. }] => {
+            assert_eq!(*transform, Transform::identity())
+        }
+        other => panic!("Expected a single untransformed rect, got {:?}", other),
+    }
+}
+
+#[test]
+fn fitted_box_scales_child() {
+    let mut fitted = FittedBox::new(BoxFit::Contain, SizedRect::new(200.0, 100.0));
+    fitted.layout(SizeConstraint::tight((100, 100)), &());
+    let layers = draw(&fitted);
+    match layers[0].borrow_commands() {
+        [RenderCommand::DrawRect {
+            transform,
+            top_left,
+            size,
+            ..
+        }] => {
+            let expected = Transform::new(0.5, 0.0, 0.0, 0.0, 0.5, 25.0);
+            assert_eq!(*transform, expected);
+            assert_eq!(*top_left * *transform, Point::new(0.0, 25.0));
+            assert_eq!(*size, Size::new(200.0, 100.0));
+        }
+        other => panic!("Expected a single rect, got {:?}", other),
+    }
+}
+
+#[test]
+fn clean_sibling_layout_is_skipped() {
+    let mut column = LayoutCache::new::<TestConfig>(Column::new::<TestConfig>(vec![
+        LayoutCache::new::<TestConfig>(LayoutCounter::new(1)),
+        LayoutCache::new::<TestConfig>(LayoutCounter::new(2)),
+    ]));
+    let drawer = GuiDrawer::new();
+    drawer.layout::<TestConfig, _>(&mut column, &());
+    assert!(!RenderWidget::<TestConfig>::is_dirty(&column));
+    drawer.layout::<TestConfig, _>(&mut column, &());
+    column.child_mut().children[1].1.child_mut().id = 3;
+    assert!(RenderWidget::<TestConfig>::is_dirty(&column));
+    drawer.layout::<TestConfig, _>(&mut column, &());
+    let layouts: Vec<_> = column
+        .child()
+        .children
+        .iter()
+        .map(|(_, child)| (child.child().id, child.child().layouts))
+        .collect();
+    assert_eq!(layouts, vec![(1, 1), (3, 2)]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "AlignBox was drawn before it was laid out")]
+fn draw_without_layout_detected() {
+    let aligned = SizedRect::new(100.0, 100.0).aligned(Alignment::Middle, Alignment::Middle);
+    draw(&aligned);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Layer height -121 is reserved for astro_gui")]
+fn shadow_below_min_app_rejected() {
+    let mut context = DrawContext::new();
+    context.set_layer(LayerId::MIN_APP);
+    context.draw_shadow(0, (10, 10), 4.0, (2, 2), Color::BLACK);
+}
+
+#[test]
+fn progress_bar_in_row_is_finite() {
+    let bar = ProgressBar::new(0.5, Color::BLACK, Color::WHITE, 10.0);
+    let mut row = Row::new::<TestConfig>(vec![bar]);
+    let size = RenderWidget::<TestConfig>::layout(&mut row, SizeConstraint::loose((300, 50)), &());
+    assert_eq!(size, Size::new(0.0, 10.0));
+    assert!(rect_sizes(&draw(&row)).iter().all(|size| size.is_finite()));
+
+    let bar = ProgressBar::new(0.5, Color::BLACK, Color::WHITE, 10.0);
+    let mut row = Row::with_fill::<TestConfig>(vec![bar], 0);
+    RenderWidget::<TestConfig>::layout(&mut row, SizeConstraint::loose((300, 50)), &());
+    let sizes = rect_sizes(&draw(&row));
+    assert_eq!(sizes, vec![Size::new(300.0, 10.0), Size::new(150.0, 10.0)]);
+}
+
+#[test]
+fn progress_bar_ignores_nan_progress() {
+    let mut bar = ProgressBar::new(f32::NAN, Color::BLACK, Color::WHITE, 10.0);
+    RenderWidget::<TestConfig>::layout(&mut bar, SizeConstraint::loose((300, 50)), &());
+    assert_eq!(rect_sizes(&draw(&bar)), vec![Size::new(300.0, 10.0)]);
+}
+
+#[test]
+fn cached_draw_under_scaled_parent() {
+    let mut widget = CachedDraw::new(DrawCounter(Cell::new(0)));
+    RenderWidget::<TestConfig>::layout(&mut widget, SizeConstraint::loose((800, 600)), &());
+    draw(&widget);
+    let mut context = DrawContext::new();
+    context.scale((2, 2));
+    context.draw_child(&widget, 0, (10, 10));
+    let scaled = context.finalize().flatten();
+    assert_eq!(widget.child.0.get(), 1);
+    // The child's translation is scaled along with everything else it draws.
+    let expected = Transform::new(2.0, 0.0, 2.0, 0.0, 2.0, 4.0);
+    assert_eq!(
+        count_commands(
+            &scaled,
+            |c| matches!(c, RenderCommand::DrawRect { transform, .. } if *transform == expected)
+        ),
+        1
+    );
+}
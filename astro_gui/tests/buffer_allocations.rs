@@ -0,0 +1,91 @@
+//! Lives in its own test binary so that the counting allocator does not affect other tests.
+
+use astro_gui::*;
+use astro_math::Size;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+/// Counts allocations and reallocations made while `COUNTING` is set.
+struct CountingAllocator;
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if COUNTING.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct TestConfig;
+
+impl GuiConfig for TestConfig {
+    type Renderer = ();
+    type Measurer = ();
+}
+
+/// Draws one rect on each of several layers.
+struct LayeredRects;
+
+impl RenderWidget<TestConfig> for LayeredRects {
+    fn layout(&mut self, _constraint: SizeConstraint, _measurer: &()) -> Size {
+        Size::new(100.0, 100.0)
+    }
+
+    fn draw(&self, drawer: &mut DrawContext) {
+        for layer in 0..3 {
+            drawer.set_layer(layer);
+            drawer.draw_rect(0, (100, 100));
+        }
+        drawer.begin_layer_group(100);
+        drawer.draw_rect(0, (10, 10));
+        drawer.end_layer_group();
+    }
+}
+
+/// Draws a frame, returning its layers and how many allocations drawing it took.
+fn counted_frame(drawer: &GuiDrawer, pool: &mut BufferPool) -> (Vec<Layer>, usize) {
+    ALLOCATIONS.store(0, Ordering::SeqCst);
+    COUNTING.store(true, Ordering::SeqCst);
+    let layers = drawer.draw_with_buffers::<TestConfig, _>(&LayeredRects, pool);
+    COUNTING.store(false, Ordering::SeqCst);
+    (layers, ALLOCATIONS.load(Ordering::SeqCst))
+}
+
+#[test]
+fn recycled_frame_allocates_no_layer_buffers() {
+    let drawer = GuiDrawer::new();
+    let mut pool = BufferPool::new();
+    let (first, fresh) = counted_frame(&drawer, &mut pool);
+    let num_layers = first.len();
+    assert_eq!(num_layers, 4);
+    // A fresh frame allocates the same amount every time, one buffer per layer plus bookkeeping.
+    let (_, fresh_again) = counted_frame(&drawer, &mut BufferPool::new());
+    assert_eq!(fresh_again, fresh);
+
+    pool.recycle(first);
+    let (second, recycled) = counted_frame(&drawer, &mut pool);
+    assert_eq!(second.len(), num_layers);
+    assert!(pool.is_empty());
+    // Only the layer buffers are saved, and none of the recycled ones needed to grow.
+    assert_eq!(fresh - recycled, num_layers);
+}
@@ -36,13 +36,173 @@ impl Transform {
         self * Self::translate(offset)
     }
 
+    /// True if this transform only moves things around, without scaling, rotating or skewing.
+    pub fn is_translation(&self) -> bool {
+        self.xx == 1.0 && self.yx == 0.0 && self.xy == 0.0 && self.yy == 1.0
+    }
+
     pub const fn scale(amount: Size) -> Self {
         Self::new(amount.x, 0.0, 0.0, 0.0, amount.y, 0.0)
     }
 
+    /// False if any component is infinite or NaN.
+    pub fn is_finite(&self) -> bool {
+        [self.xx, self.yx, self.ix, self.xy, self.yy, self.iy]
+            .iter()
+            .all(|component| component.is_finite())
+    }
+
     pub fn scaled(self, amount: Size) -> Self {
         self * Self::scale(amount)
     }
+
+    /// Linearly interpolates each component separately. This works well for translation and
+    /// scaling, but rotations will not stay rigid partway through. Interpolating decomposed
+    /// transforms would fix that.
+    pub fn lerp(self, target: Transform, t: f32) -> Self {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        Self::new(
+            lerp(self.xx, target.xx),
+            lerp(self.yx, target.yx),
+            lerp(self.ix, target.ix),
+            lerp(self.xy, target.xy),
+            lerp(self.yy, target.yy),
+            lerp(self.iy, target.iy),
+        )
+    }
+
+    /// Splits this transform into a scale, followed by a rotation, followed by a translation. Any
+    /// shear is lost. A mirrored transform (one with a negative determinant) is represented with a
+    /// negative y scale.
+    pub fn decompose(&self) -> DecomposedTransform {
+        let translation = Vec2::new(self.ix, self.iy);
+        let scale_x = self.xx.hypot(self.xy);
+        if scale_x == 0.0 {
+            // The x axis collapses to a point, so use the y axis to find the rotation instead.
+            return DecomposedTransform {
+                translation,
+                rotation: (-self.yx).atan2(self.yy),
+                scale: Vec2::new(0.0, self.yx.hypot(self.yy)),
+            };
+        }
+        let determinant = self.xx * self.yy - self.yx * self.xy;
+        DecomposedTransform {
+            translation,
+            rotation: self.xy.atan2(self.xx),
+            scale: Vec2::new(scale_x, determinant / scale_x),
+        }
+    }
+
+    /// Returns the smallest axis-aligned rectangle containing `rect` after it is transformed.
+    pub fn transform_bounds(&self, rect: Rect) -> Rect {
+        let bottom_right = rect.bottom_right();
+        let corners = [
+            rect.top_left,
+            Vec2::new(bottom_right.x, rect.top_left.y),
+            Vec2::new(rect.top_left.x, bottom_right.y),
+            bottom_right,
+        ];
+        let mut result = Rect::new(corners[0] * *self, 0);
+        for &corner in &corners[1..] {
+            result = result.union(Rect::new(corner * *self, 0));
+        }
+        result
+    }
+
+    /// Returns the transform which undoes this one, or `None` if this transform collapses
+    /// everything onto a line or a point.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.xx * self.yy - self.yx * self.xy;
+        if determinant == 0.0 {
+            return None;
+        }
+        let (xx, yx) = (self.yy / determinant, -self.yx / determinant);
+        let (xy, yy) = (-self.xy / determinant, self.xx / determinant);
+        Some(Self::new(
+            xx,
+            yx,
+            -(self.ix * xx + self.iy * yx),
+            xy,
+            yy,
+            -(self.ix * xy + self.iy * yy),
+        ))
+    }
+
+    /// Returns the transform which scales and moves `src` so that it exactly covers `dst`, or
+    /// `None` if `src` has no area.
+    pub fn from_rect_to_rect(src: Rect, dst: Rect) -> Option<Self> {
+        if src.size.x == 0.0 || src.size.y == 0.0 {
+            return None;
+        }
+        let scale = dst.size / src.size;
+        let offset = dst.top_left - src.top_left * scale;
+        Some(Self::new(scale.x, 0.0, offset.x, 0.0, scale.y, offset.y))
+    }
+
+    /// The inverse of `decompose`: scales, then rotates, then translates. Any transform without
+    /// shear survives the round trip, up to rounding errors.
+    pub fn from_decomposed(parts: DecomposedTransform) -> Self {
+        let (sin, cos) = parts.rotation.sin_cos();
+        let scale = parts.scale;
+        Self::new(
+            scale.x * cos,
+            -scale.y * sin,
+            parts.translation.x,
+            scale.x * sin,
+            scale.y * cos,
+            parts.translation.y,
+        )
+    }
+}
+
+/// The result of `Transform::decompose`. Rotation is in radians, positive values rotate the x axis
+/// towards the y axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecomposedTransform {
+    pub translation: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+/// An axis-aligned rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub top_left: Point,
+    pub size: Size,
+}
+
+impl Rect {
+    pub fn new(top_left: impl Into<Point>, size: impl Into<Size>) -> Self {
+        Self {
+            top_left: top_left.into(),
+            size: size.into(),
+        }
+    }
+
+    pub fn bottom_right(&self) -> Point {
+        self.top_left + self.size
+    }
+
+    /// True if `point` is inside this rectangle. Points on the top and left edges are inside,
+    /// points on the bottom and right edges are not.
+    pub fn contains(&self, point: Point) -> bool {
+        let bottom_right = self.bottom_right();
+        point.x >= self.top_left.x
+            && point.y >= self.top_left.y
+            && point.x < bottom_right.x
+            && point.y < bottom_right.y
+    }
+
+    /// Returns the smallest rectangle containing both this one and `other`.
+    pub fn union(self, other: Rect) -> Rect {
+        let (a, b) = (self.bottom_right(), other.bottom_right());
+        let top_left = Vec2::new(
+            self.top_left.x.min(other.top_left.x),
+            self.top_left.y.min(other.top_left.y),
+        );
+        let bottom_right = Vec2::new(a.x.max(b.x), a.y.max(b.y));
+        Self::new(top_left, bottom_right - top_left)
+    }
 }
 
 impl Mul for Transform {
@@ -53,7 +213,7 @@ impl Mul for Transform {
             yx: self.yx * rhs.xx + self.yy * rhs.yx,
             ix: self.ix * rhs.xx + self.iy * rhs.yx + rhs.ix,
             xy: self.xx * rhs.xy + self.xy * rhs.yy,
-            yy: self.xy * rhs.yx + self.yy * rhs.yy,
+            yy: self.yx * rhs.xy + self.yy * rhs.yy,
             iy: self.ix * rhs.xy + self.iy * rhs.yy + rhs.iy,
         }
     }
@@ -78,6 +238,30 @@ impl Vec2 {
     pub fn new(x: f32, y: f32) -> Self {
         Self { x, y }
     }
+
+    /// False if either component is infinite or NaN.
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+
+    /// Rounds both components to the nearest whole number, useful for snapping to pixels.
+    pub fn round(self) -> Self {
+        Self::new(self.x.round(), self.y.round())
+    }
+
+    pub fn floor(self) -> Self {
+        Self::new(self.x.floor(), self.y.floor())
+    }
+
+    pub fn ceil(self) -> Self {
+        Self::new(self.x.ceil(), self.y.ceil())
+    }
+
+    /// Converts both components to integers, rounding towards zero. Use `round`, `floor` or
+    /// `ceil` first to pick a different rounding mode.
+    pub fn to_i32_array(self) -> [i32; 2] {
+        [self.x as i32, self.y as i32]
+    }
 }
 
 macro_rules! from_scalar {
@@ -154,9 +338,78 @@ impl MulAssign<Transform> for Vec2 {
     }
 }
 
+#[cfg(feature = "mint")]
+impl From<mint::Vector2<f32>> for Vec2 {
+    fn from(other: mint::Vector2<f32>) -> Self {
+        Self::new(other.x, other.y)
+    }
+}
+
+#[cfg(feature = "mint")]
+impl From<Vec2> for mint::Vector2<f32> {
+    fn from(other: Vec2) -> Self {
+        Self {
+            x: other.x,
+            y: other.y,
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+impl From<mint::Point2<f32>> for Vec2 {
+    fn from(other: mint::Point2<f32>) -> Self {
+        Self::new(other.x, other.y)
+    }
+}
+
+#[cfg(feature = "mint")]
+impl From<Vec2> for mint::Point2<f32> {
+    fn from(other: Vec2) -> Self {
+        Self {
+            x: other.x,
+            y: other.y,
+        }
+    }
+}
+
+/// Points are multiplied on the left of transforms, so each row of the matrix is one of the
+/// transform's axes, followed by the translation.
+#[cfg(feature = "mint")]
+impl From<mint::RowMatrix3x2<f32>> for Transform {
+    fn from(other: mint::RowMatrix3x2<f32>) -> Self {
+        Self::new(
+            other.x.x, other.y.x, other.z.x, other.x.y, other.y.y, other.z.y,
+        )
+    }
+}
+
+#[cfg(feature = "mint")]
+impl From<Transform> for mint::RowMatrix3x2<f32> {
+    fn from(other: Transform) -> Self {
+        Self {
+            x: Vec2::new(other.xx, other.xy).into(),
+            y: Vec2::new(other.yx, other.yy).into(),
+            z: Vec2::new(other.ix, other.iy).into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Transform, Vec2};
+    use super::{DecomposedTransform, Rect, Transform, Vec2};
+
+    fn assert_close(a: Transform, b: Transform) {
+        let a = [a.xx, a.yx, a.ix, a.xy, a.yy, a.iy];
+        let b = [b.xx, b.yx, b.ix, b.xy, b.yy, b.iy];
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn rotate(angle: f32) -> Transform {
+        let (sin, cos) = angle.sin_cos();
+        Transform::new(cos, -sin, 0.0, sin, cos, 0.0)
+    }
 
     #[test]
     fn translate() {
@@ -205,4 +458,138 @@ mod tests {
             Vec2::new(3.0, 8.0)
         )
     }
+
+    #[test]
+    fn rotations_compose() {
+        assert_close(rotate(0.25) * rotate(0.5), rotate(0.75));
+    }
+
+    #[test]
+    fn multiply_matches_applying_in_order() {
+        // Every component is different so that mixing up any two of them changes the result.
+        let a = Transform::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        let b = Transform::new(7.0, 8.0, 9.0, 10.0, 11.0, 12.0);
+        let point = Vec2::new(13.0, 14.0);
+        assert_eq!(point * (a * b), (point * a) * b);
+    }
+
+    #[test]
+    fn decompose_round_trip() {
+        let transform = Transform::scale(Vec2::new(2.0, 3.0))
+            * rotate(0.5)
+            * Transform::translate(Vec2::new(4.0, 5.0));
+        let parts = transform.decompose();
+        assert!((parts.rotation - 0.5).abs() < 1e-5);
+        assert!((parts.scale.x - 2.0).abs() < 1e-5);
+        assert!((parts.scale.y - 3.0).abs() < 1e-5);
+        assert_eq!(parts.translation, Vec2::new(4.0, 5.0));
+        assert_close(Transform::from_decomposed(parts), transform);
+    }
+
+    #[test]
+    fn decompose_mirrored() {
+        let transform = Transform::scale(Vec2::new(-2.0, 1.0));
+        let parts = transform.decompose();
+        assert!(parts.scale.x > 0.0);
+        assert!(parts.scale.y < 0.0);
+        assert_close(Transform::from_decomposed(parts), transform);
+    }
+
+    #[test]
+    fn decompose_degenerate() {
+        let parts = Transform::scale(Vec2::new(0.0, 2.0)).decompose();
+        assert_eq!(
+            parts,
+            DecomposedTransform {
+                translation: Vec2::new(0.0, 0.0),
+                rotation: 0.0,
+                scale: Vec2::new(0.0, 2.0),
+            }
+        );
+    }
+
+    #[test]
+    fn lerp_translate() {
+        let target = Transform::translate(Vec2::new(4.0, -2.0));
+        assert_eq!(
+            Transform::identity().lerp(target, 0.0),
+            Transform::identity()
+        );
+        assert_eq!(
+            Transform::identity().lerp(target, 0.5),
+            Transform::translate(Vec2::new(2.0, -1.0))
+        );
+        assert_eq!(Transform::identity().lerp(target, 1.0), target);
+    }
+
+    #[test]
+    fn pixel_snapping() {
+        let rounded = Vec2::new(1.4, 1.6).round();
+        assert_eq!(rounded, Vec2::new(1.0, 2.0));
+        assert_eq!(rounded.to_i32_array(), [1, 2]);
+        assert_eq!(Vec2::new(1.6, -1.4).floor(), Vec2::new(1.0, -2.0));
+        assert_eq!(Vec2::new(1.4, -1.6).ceil(), Vec2::new(2.0, -1.0));
+    }
+
+    #[test]
+    fn bounds_of_rotated_rect() {
+        let rect = Rect::new((1, 0), (2, 1));
+        let bounds = rotate(std::f32::consts::FRAC_PI_2).transform_bounds(rect);
+        let expected = Rect::new((-1, 1), (1, 2));
+        assert!((bounds.top_left - expected.top_left).x.abs() < 1e-5);
+        assert!((bounds.top_left - expected.top_left).y.abs() < 1e-5);
+        assert!((bounds.size - expected.size).x.abs() < 1e-5);
+        assert!((bounds.size - expected.size).y.abs() < 1e-5);
+    }
+
+    #[test]
+    fn finite_checks() {
+        assert!(Vec2::new(1.0, -2.0).is_finite());
+        assert!(!Vec2::new(1.0, f32::INFINITY).is_finite());
+        assert!(Transform::scale(Vec2::new(2.0, 3.0)).is_finite());
+        assert!(!Transform::translate(Vec2::new(f32::NAN, 0.0)).is_finite());
+    }
+
+    #[test]
+    fn inverse_round_trip() {
+        let transform = Transform::scale(Vec2::new(2.0, 3.0))
+            * rotate(0.5)
+            * Transform::translate(Vec2::new(4.0, 5.0));
+        let inverse = transform.inverse().unwrap();
+        assert_close(transform * inverse, Transform::identity());
+        assert_close(inverse * transform, Transform::identity());
+        assert_eq!(Transform::scale(Vec2::new(0.0, 1.0)).inverse(), None);
+    }
+
+    #[test]
+    fn rect_to_rect() {
+        let dst = Rect::new((10, 10), (20, 40));
+        let transform = Transform::from_rect_to_rect(Rect::new(0, 1), dst).unwrap();
+        assert_eq!(Vec2::new(0.0, 0.0) * transform, Vec2::new(10.0, 10.0));
+        assert_eq!(Vec2::new(1.0, 1.0) * transform, Vec2::new(30.0, 50.0));
+        assert_eq!(
+            Transform::from_rect_to_rect(Rect::new(0, (0, 1)), dst),
+            None
+        );
+    }
+    #[cfg(feature = "mint")]
+    #[test]
+    fn mint_round_trip() {
+        let vector = Vec2::new(1.0, -2.0);
+        let as_mint: mint::Vector2<f32> = vector.into();
+        assert_eq!(Vec2::from(as_mint), vector);
+        let as_mint: mint::Point2<f32> = vector.into();
+        assert_eq!(Vec2::from(as_mint), vector);
+
+        let transform = Transform::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        let as_mint: mint::RowMatrix3x2<f32> = transform.into();
+        assert_eq!(Transform::from(as_mint), transform);
+        // Transforming a point must give the same result as multiplying [x y 1] by the matrix.
+        let point = Vec2::new(7.0, 8.0);
+        let expected = Vec2::new(
+            point.x * as_mint.x.x + point.y * as_mint.y.x + as_mint.z.x,
+            point.x * as_mint.x.y + point.y * as_mint.y.y + as_mint.z.y,
+        );
+        assert_eq!(point * transform, expected);
+    }
 }
@@ -4,13 +4,14 @@ struct Config;
 
 impl GuiConfig for Config {
     type Renderer = ();
+    type Measurer = ();
 }
 
 fn main() {
-    let list = Column::new::<Config>(vec![DebugRect, DebugRect, DebugRect]);
+    let list = Column::new::<Config>(vec![DebugRect::new(), DebugRect::new(), DebugRect::new()]);
     let mut root = AlignBox::new::<Config>(Center, Middle, list);
     let drawer = astro_gui::GuiDrawer::new();
-    drawer.layout::<Config, _>(&mut root);
+    drawer.layout::<Config, _>(&mut root, &());
     let commands = drawer.draw::<Config, _>(&root);
-    println!("{:#?}", commands);
+    print!("{}", debug_tree(&commands));
 }
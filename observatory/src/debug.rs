@@ -0,0 +1,21 @@
+//! Tools for finding problems with reactive graphs. Only available in debug builds, or in any
+//! build with the `ptr-debug-always` feature enabled.
+#![cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static LIVE_NODES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns how many observables and derivations currently exist. Tests can check that this goes
+/// back to what it was before a graph was built once the graph is dropped, to catch leaks.
+pub fn live_node_count() -> usize {
+    LIVE_NODES.load(Ordering::Relaxed)
+}
+
+pub(crate) fn node_created() {
+    LIVE_NODES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn node_dropped() {
+    LIVE_NODES.fetch_sub(1, Ordering::Relaxed);
+}
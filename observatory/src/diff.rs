@@ -0,0 +1,83 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// One step of turning a list of keys into another, see `diff_keyed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffOp<K> {
+    /// `key` is not in the old list and is at `index` in the new list.
+    Insert { index: usize, key: K },
+    /// `key` is at `index` in the old list and is not in the new list.
+    Remove { index: usize, key: K },
+    /// `key` is at `from` in the old list and at `to` in the new list, and its order relative to
+    /// the other keys in both lists changed.
+    Move { from: usize, to: usize, key: K },
+}
+
+/// Compares two lists of unique keys. Keys which keep their relative order form the longest
+/// common subsequence of both lists and produce no operations, everything else is inserted,
+/// removed or moved. Indexes refer to the original lists, not to the list partway through applying
+/// the operations. Removals are listed first in old order, followed by insertions and moves in new
+/// order.
+pub fn diff_keyed<K: Eq + Hash + Clone>(old: &[K], new: &[K]) -> Vec<DiffOp<K>> {
+    let new_indexes: HashMap<&K, usize> = new.iter().enumerate().map(|(i, k)| (k, i)).collect();
+    let old_indexes: HashMap<&K, usize> = old.iter().enumerate().map(|(i, k)| (k, i)).collect();
+
+    let mut ops = Vec::new();
+    // New positions of the keys which are in both lists, in old order.
+    let mut kept = Vec::new();
+    for (index, key) in old.iter().enumerate() {
+        match new_indexes.get(key) {
+            Some(&new_index) => kept.push(new_index),
+            None => ops.push(DiffOp::Remove {
+                index,
+                key: key.clone(),
+            }),
+        }
+    }
+
+    let stationary = longest_increasing_subsequence(&kept);
+    for (index, key) in new.iter().enumerate() {
+        match old_indexes.get(key) {
+            None => ops.push(DiffOp::Insert {
+                index,
+                key: key.clone(),
+            }),
+            Some(&from) if !stationary[index] => ops.push(DiffOp::Move {
+                from,
+                to: index,
+                key: key.clone(),
+            }),
+            Some(..) => (),
+        }
+    }
+    ops
+}
+
+/// Returns a list where `result[value]` is true if `value` is part of a longest strictly
+/// increasing subsequence of `values`. Values must be unique.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<bool> {
+    let len = values.iter().max().map_or(0, |&max| max + 1);
+    // tails[n] is the index of the smallest value ending an increasing subsequence of length n + 1.
+    let mut tails: Vec<usize> = Vec::new();
+    // The index of the value before each value in the best subsequence ending with it.
+    let mut previous = vec![None; values.len()];
+    for (index, &value) in values.iter().enumerate() {
+        let length = match tails.binary_search_by(|&tail| values[tail].cmp(&value)) {
+            Ok(length) | Err(length) => length,
+        };
+        if length > 0 {
+            previous[index] = Some(tails[length - 1]);
+        }
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+    let mut result = vec![false; len];
+    let mut current = tails.last().copied();
+    while let Some(index) = current {
+        result[values[index]] = true;
+        current = previous[index];
+    }
+    result
+}
@@ -0,0 +1,46 @@
+use crate::{observable::ObservableRef, IsUnchanged, ObservablePtr};
+use std::{cell::RefCell, rc::Rc};
+
+/// An observable whose value comes from somewhere observatory can't track, like the clock or the
+/// filesystem. The value is computed once on creation and again every time `invalidate` is called.
+/// Cloning this creates another pointer to the same value.
+pub struct ExternalPtr<T: 'static> {
+    value: ObservablePtr<T>,
+    compute_value: Rc<RefCell<dyn FnMut() -> T>>,
+}
+
+impl<T: 'static> Clone for ExternalPtr<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: ObservablePtr::clone(&self.value),
+            compute_value: Rc::clone(&self.compute_value),
+        }
+    }
+}
+
+impl<T: IsUnchanged + 'static> ExternalPtr<T> {
+    pub fn new(mut compute_value: impl FnMut() -> T + 'static) -> Self {
+        Self {
+            value: ObservablePtr::new(compute_value()),
+            compute_value: Rc::new(RefCell::new(compute_value)),
+        }
+    }
+
+    /// Recomputes the value. Derivations depending on it are updated if it changed.
+    pub fn invalidate(&self) {
+        let new_value = (*self.compute_value.borrow_mut())();
+        self.value.set(new_value);
+    }
+
+    pub fn borrow(&self) -> ObservableRef<'_, T> {
+        self.value.borrow()
+    }
+
+    pub fn borrow_untracked(&self) -> ObservableRef<'_, T> {
+        self.value.borrow_untracked()
+    }
+
+    pub fn is_observed(&self) -> bool {
+        self.value.is_observed()
+    }
+}
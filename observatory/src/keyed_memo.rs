@@ -0,0 +1,64 @@
+use crate::{derivation_dyn, DerivationDynPtr, IsUnchanged};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    rc::Rc,
+};
+
+type ComputeFn<I, V> = Rc<RefCell<dyn FnMut(&I) -> V>>;
+
+/// Caches a derivation for each item of a collection, see `keyed_memo`.
+pub struct KeyedMemo<I, K, V: IsUnchanged + 'static> {
+    key_fn: Box<dyn FnMut(&I) -> K>,
+    compute_fn: ComputeFn<I, V>,
+    entries: HashMap<K, DerivationDynPtr<V>>,
+}
+
+/// Creates a cache which computes a value for each item of a collection using `compute_fn`, inside
+/// a separate derivation for each key returned by `key_fn`. When an observable borrowed while
+/// computing the value of one item changes, only that item's value is recomputed. The item an
+/// entry was created from is kept around, so anything about an item which can change without
+/// changing its key should be stored in observables.
+pub fn keyed_memo<I, K, V>(
+    key_fn: impl FnMut(&I) -> K + 'static,
+    compute_fn: impl FnMut(&I) -> V + 'static,
+) -> KeyedMemo<I, K, V>
+where
+    K: Eq + Hash,
+    V: IsUnchanged + 'static,
+{
+    KeyedMemo {
+        key_fn: Box::new(key_fn),
+        compute_fn: Rc::new(RefCell::new(compute_fn)),
+        entries: HashMap::new(),
+    }
+}
+
+impl<I: Clone + 'static, K: Eq + Hash, V: IsUnchanged + 'static> KeyedMemo<I, K, V> {
+    /// Returns the derivation computing the value for `item`, creating it if no item with the
+    /// same key has been seen before.
+    pub fn get(&mut self, item: &I) -> DerivationDynPtr<V> {
+        let key = (self.key_fn)(item);
+        let compute_fn = &self.compute_fn;
+        let entry = self.entries.entry(key).or_insert_with(|| {
+            let (item, compute_fn) = (item.clone(), Rc::clone(compute_fn));
+            derivation_dyn(move || (compute_fn.borrow_mut())(&item))
+        });
+        entry.clone()
+    }
+
+    /// Forgets the entries of all keys which don't belong to any of `items`.
+    pub fn retain<'a>(&mut self, items: impl IntoIterator<Item = &'a I>) {
+        let keep: HashSet<K> = items.into_iter().map(&mut self.key_fn).collect();
+        self.entries.retain(|key, _| keep.contains(key));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
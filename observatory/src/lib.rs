@@ -1,7 +1,6 @@
 #![feature(arc_new_cyclic)]
-#![feature(unsize)]
-#![feature(coerce_unsized)]
-#![feature(test)]
+#![cfg_attr(test, feature(test))]
+#![allow(stable_features)]
 
 //! Provides MobX style observables. Example:
 //! ```rust
@@ -112,17 +111,34 @@
 //! ```
 
 mod bench;
+pub mod debug;
+mod diff;
+mod external;
+mod keyed_memo;
 mod observable;
+mod observable_map;
+mod observable_vec;
 mod observer;
 #[doc(hidden)]
 pub mod ptr_util;
+mod pull;
+mod reaction;
 mod static_state;
 mod tests;
 
-pub use observable::ObservablePtr;
+pub use diff::{diff_keyed, DiffOp};
+pub use external::ExternalPtr;
+pub use keyed_memo::{keyed_memo, KeyedMemo};
+pub use observable::{ObservablePtr, ObservableRef, RefProjection, WeakObservable};
+pub use observable_map::ObservableMap;
+pub use observable_vec::{ElementRefMut, ObservableVec};
 pub use observer::DerivationPtr;
 pub use observer::IsUnchanged;
-pub use static_state::{init, is_initialized};
+pub use pull::{derivation_pull, PullDerivationPtr};
+pub use reaction::{
+    autorun, reaction, reaction_debounced, DebouncedReactionHandle, ReactionHandle,
+};
+pub use static_state::{init, is_initialized, max_update_depth, set_max_update_depth};
 
 pub type DerivationDynPtr<T> = DerivationPtr<T, Box<dyn FnMut() -> T + 'static>>;
 
@@ -130,6 +146,28 @@ pub fn observable<T: 'static>(value: T) -> ObservablePtr<T> {
     ObservablePtr::new(value)
 }
 
+/// Like `observable`, but the initial value is computed by `initial_value`, which is called once.
+pub fn observable_with<T: 'static>(initial_value: impl FnOnce() -> T) -> ObservablePtr<T> {
+    ObservablePtr::new(initial_value())
+}
+
+/// Creates a view of part of `source` which can be borrowed without copying, see `RefProjection`.
+pub fn derivation_ref<T: 'static, U: ?Sized + 'static>(
+    source: &ObservablePtr<T>,
+    project: impl Fn(&T) -> &U + 'static,
+) -> RefProjection<T, U> {
+    source.project(project)
+}
+
+/// Creates an observable whose value is computed by `compute_value`, which may read from sources
+/// that can't be tracked automatically. Call `ExternalPtr::invalidate` whenever such a source
+/// changes.
+pub fn observable_external<T: IsUnchanged + 'static>(
+    compute_value: impl FnMut() -> T + 'static,
+) -> ExternalPtr<T> {
+    ExternalPtr::new(compute_value)
+}
+
 pub fn derivation<T: IsUnchanged + 'static, F: FnMut() -> T + 'static>(
     compute_value: F,
 ) -> DerivationPtr<T, F> {
@@ -142,6 +180,101 @@ pub fn derivation_dyn<T: IsUnchanged + 'static, F: FnMut() -> T + 'static>(
     DerivationPtr::new_dyn(compute_value)
 }
 
+/// Creates a derivation computed from the values of two observables.
+pub fn combine2<A, B, R, F>(
+    a: &ObservablePtr<A>,
+    b: &ObservablePtr<B>,
+    mut f: F,
+) -> DerivationDynPtr<R>
+where
+    A: 'static,
+    B: 'static,
+    R: IsUnchanged + 'static,
+    F: FnMut(&A, &B) -> R + 'static,
+{
+    let (a, b) = (a.clone(), b.clone());
+    derivation_dyn(move || f(&*a.borrow(), &*b.borrow()))
+}
+
+/// Creates a derivation computed from the values of three observables.
+pub fn combine3<A, B, C, R, F>(
+    a: &ObservablePtr<A>,
+    b: &ObservablePtr<B>,
+    c: &ObservablePtr<C>,
+    mut f: F,
+) -> DerivationDynPtr<R>
+where
+    A: 'static,
+    B: 'static,
+    C: 'static,
+    R: IsUnchanged + 'static,
+    F: FnMut(&A, &B, &C) -> R + 'static,
+{
+    let (a, b, c) = (a.clone(), b.clone(), c.clone());
+    derivation_dyn(move || f(&*a.borrow(), &*b.borrow(), &*c.borrow()))
+}
+
+/// Creates a derivation which is true when all of the provided observables are true.
+pub fn all(observables: &[ObservablePtr<bool>]) -> DerivationDynPtr<bool> {
+    let observables = observables.to_vec();
+    derivation_dyn(move || observables.iter().all(|observable| *observable.borrow()))
+}
+
+/// Creates a derivation which is true when any of the provided observables are true.
+pub fn any(observables: &[ObservablePtr<bool>]) -> DerivationDynPtr<bool> {
+    let observables = observables.to_vec();
+    derivation_dyn(move || observables.iter().any(|observable| *observable.borrow()))
+}
+
+/// Creates a derivation which is true when the provided observable is false.
+pub fn not(observable: &ObservablePtr<bool>) -> DerivationDynPtr<bool> {
+    let observable = observable.clone();
+    derivation_dyn(move || !*observable.borrow())
+}
+
+/// Runs `f` without recording anything it borrows as a dependency of the derivation currently
+/// being computed. Borrowing observables inside `f` is also allowed outside of derivations.
+pub fn untracked<R>(f: impl FnOnce() -> R) -> R {
+    let _frame = static_state::push_untracked();
+    f()
+}
+
+/// Runs `f`, which is allowed to modify observables even when called while computing a
+/// derivation. Derivations depending on the modified observables are only updated once `f`
+/// returns, and only once no matter how many modifications were made. Like `untracked`, nothing
+/// borrowed inside `f` is recorded as a dependency.
+pub fn action<R>(f: impl FnOnce() -> R) -> R {
+    let _action = static_state::begin_action();
+    f()
+}
+
+/// Stops derivations from updating until `resume` is called, like a long-running `action`. Unlike
+/// `action`, this does not allow modifying observables while computing a derivation. Every call
+/// must be matched by a call to `resume`.
+pub fn suspend() {
+    static_state::suspend();
+}
+
+/// Undoes a call to `suspend`. Once every `suspend` has been matched, all derivations depending
+/// on observables modified in the meantime are updated, each of them only once.
+pub fn resume() {
+    static_state::resume();
+}
+
+/// Runs `f` with updates suspended, see `suspend`. Updates are resumed even if `f` panics.
+pub fn suspended<R>(f: impl FnOnce() -> R) -> R {
+    struct ResumeOnDrop;
+    impl Drop for ResumeOnDrop {
+        fn drop(&mut self) {
+            resume();
+        }
+    }
+
+    suspend();
+    let _resume = ResumeOnDrop;
+    f()
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __derivation_with_ptrs_parse {
@@ -169,3 +302,22 @@ macro_rules! derivation_with_ptrs_dyn {
         $crate::__derivation_with_ptrs_parse!(derivation_dyn () $($args)*)
     };
 }
+
+/// Creates a derivation which formats the values of the listed pointers into a string:
+/// ```rust
+/// use observatory as o;
+/// o::init();
+/// let first_name = o::observable("William");
+/// let last_name = o::observable("Riker");
+/// let full_name = o::format_derivation!("{} {}", first_name, last_name);
+/// assert_eq!(*full_name.borrow_untracked(), "William Riker");
+/// ```
+#[macro_export]
+macro_rules! format_derivation {
+    ($format:literal $(, $ptr:ident)* $(,)?) => {
+        {
+            $crate::ptr_clone!($($ptr),*);
+            $crate::derivation(move || format!($format $(, *$ptr.borrow())*))
+        }
+    };
+}
@@ -1,9 +1,9 @@
 use crate::{
     observer::{ObserverInternalFns, ObserverList},
-    static_state,
+    static_state, DerivationDynPtr, DerivationPtr, IsUnchanged,
 };
 use std::{
-    cell::{Ref, RefCell, RefMut},
+    cell::{Cell, Ref, RefCell, RefMut},
     ops::{Deref, DerefMut},
     rc::{Rc, Weak},
 };
@@ -11,19 +11,34 @@ use std::{
 pub(crate) trait ObservableInternalFns {
     fn add_observer(&self, observer: Weak<dyn ObserverInternalFns>);
     fn remove_observer(&self, observer: &Weak<dyn ObserverInternalFns>);
+    fn broadcast_stale(&self);
+    fn broadcast_ready(&self, changed: bool);
     fn get_unique_data_address(&self) -> *const ();
 }
 
+/// Notifies observers of a modified observable and bumps its version. If an action is in progress,
+/// the second half of the notification is deferred until the action finishes.
+fn after_modified(data: Rc<dyn ObservableInternalFns>, version: &Cell<u64>) {
+    version.set(version.get() + 1);
+    data.broadcast_stale();
+    if static_state::is_batching() {
+        static_state::defer_ready(data);
+    } else {
+        data.broadcast_ready(true);
+    }
+}
+
 #[repr(C)]
 struct ObservableData<T: ?Sized> {
     observers: ObserverList,
+    version: Cell<u64>,
     value: RefCell<T>,
 }
 
-impl<T: ?Sized> ObservableData<T> {
-    fn after_modified(&self) {
-        self.observers.broadcast_stale();
-        self.observers.broadcast_ready(true);
+#[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+impl<T: ?Sized> Drop for ObservableData<T> {
+    fn drop(&mut self) {
+        crate::debug::node_dropped();
     }
 }
 
@@ -36,6 +51,14 @@ impl<T> ObservableInternalFns for ObservableData<T> {
         self.observers.remove(observer);
     }
 
+    fn broadcast_stale(&self) {
+        self.observers.broadcast_stale();
+    }
+
+    fn broadcast_ready(&self, changed: bool) {
+        self.observers.broadcast_ready(changed);
+    }
+
     fn get_unique_data_address(&self) -> *const () {
         self.value.as_ptr() as _
     }
@@ -53,6 +76,26 @@ impl<T: ?Sized + 'static> Clone for ObservablePtr<T> {
     }
 }
 
+/// A pointer to an observable which does not keep it alive, see `ObservablePtr::downgrade`.
+pub struct WeakObservable<T: ?Sized + 'static> {
+    ptr: Weak<ObservableData<T>>,
+}
+
+impl<T: ?Sized + 'static> Clone for WeakObservable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: Weak::clone(&self.ptr),
+        }
+    }
+}
+
+impl<T: ?Sized + 'static> WeakObservable<T> {
+    /// Returns a pointer to the observable, or `None` if it has already been dropped.
+    pub fn upgrade(&self) -> Option<ObservablePtr<T>> {
+        self.ptr.upgrade().map(|ptr| ObservablePtr { ptr })
+    }
+}
+
 pub struct ObservableRef<'a, T: ?Sized + 'a> {
     raw: Ref<'a, T>,
 }
@@ -63,15 +106,25 @@ impl<'a, T: ?Sized + 'a> From<Ref<'a, T>> for ObservableRef<'a, T> {
     }
 }
 
+impl<'a, T: ?Sized + 'a> ObservableRef<'a, T> {
+    /// Like `Ref::map`, narrows the borrow down to a part of the value.
+    pub fn map<U: ?Sized>(orig: Self, f: impl FnOnce(&T) -> &U) -> ObservableRef<'a, U> {
+        ObservableRef {
+            raw: Ref::map(orig.raw, f),
+        }
+    }
+}
+
 impl<'a, T: ?Sized + 'a> Deref for ObservableRef<'a, T> {
     type Target = T;
     fn deref(&self) -> &T {
-        &*self.raw
+        &self.raw
     }
 }
 
 pub struct ObservableRefMut<'a, T: ?Sized + 'a> {
-    data: Rc<ObservableData<T>>,
+    data: Rc<dyn ObservableInternalFns>,
+    version: &'a Cell<u64>,
     raw: Option<RefMut<'a, T>>,
 }
 
@@ -91,7 +144,7 @@ impl<'a, T: ?Sized + 'a> Drop for ObservableRefMut<'a, T> {
     fn drop(&mut self) {
         // Drop the reference so that observers notified of the changes can read the new data.
         self.raw = None;
-        self.data.after_modified();
+        after_modified(Rc::clone(&self.data), self.version);
     }
 }
 
@@ -99,32 +152,175 @@ impl<T: 'static> ObservablePtr<T> {
     pub fn new(value: T) -> Self {
         let bx = ObservableData {
             observers: Default::default(),
+            version: Cell::new(0),
             value: RefCell::new(value),
         };
         let ptr = Rc::new(bx);
+        #[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+        crate::debug::node_created();
         Self { ptr }
     }
 
-    pub fn borrow(&self) -> ObservableRef<T> {
+    pub fn borrow(&self) -> ObservableRef<'_, T> {
         static_state::note_observed(Rc::clone(&self.ptr) as _);
         From::from(self.ptr.value.borrow())
     }
 
-    pub fn borrow_untracked(&self) -> ObservableRef<T> {
+    pub fn borrow_untracked(&self) -> ObservableRef<'_, T> {
         From::from(self.ptr.value.borrow())
     }
 
-    pub fn borrow_mut(&self) -> ObservableRefMut<T> {
+    pub fn borrow_mut(&self) -> ObservableRefMut<'_, T> {
+        static_state::assert_mutation_allowed();
         ObservableRefMut {
-            data: Rc::clone(&self.ptr),
-            raw: Some(self.ptr.value.borrow_mut()),
+            data: Rc::clone(&self.ptr) as _,
+            version: &self.ptr.version,
+            raw: Some(self.value_mut()),
+        }
+    }
+
+    fn value_mut(&self) -> RefMut<'_, T> {
+        match self.ptr.value.try_borrow_mut() {
+            Ok(value) => value,
+            Err(_) => panic!(concat!(
+                "Observable modified while it is still borrowed, for example by a running ",
+                "derivation. Drop the borrow before calling set() or borrow_mut()."
+            )),
+        }
+    }
+
+    /// Returns a pointer which does not keep the observable alive.
+    pub fn downgrade(&self) -> WeakObservable<T> {
+        WeakObservable {
+            ptr: Rc::downgrade(&self.ptr),
+        }
+    }
+
+    /// Returns true if any derivations currently depend on the value of this observable.
+    pub fn is_observed(&self) -> bool {
+        !self.ptr.observers.is_empty()
+    }
+
+    /// Replaces the value and notifies observers without checking whether it actually changed.
+    /// `set` compares the old and new values first, which can be expensive for large values. Use
+    /// this instead when you already know the value is different, or when spurious updates are
+    /// cheaper than the comparison.
+    pub fn set_unchecked(&self, new_value: T) {
+        static_state::assert_mutation_allowed();
+        *self.value_mut() = new_value;
+        after_modified(Rc::clone(&self.ptr) as _, &self.ptr.version);
+    }
+
+    /// Increases every time the value is modified. Can be stored and compared against later to
+    /// cheaply check whether the value has changed in the meantime.
+    pub fn version(&self) -> u64 {
+        self.ptr.version.get()
+    }
+
+    /// Returns a derivation holding the part of this observable's value picked out by `f`.
+    /// Derivations which only borrow the selection are not recomputed when the value changes in
+    /// a way that leaves the selected part unchanged.
+    pub fn select<U: IsUnchanged + 'static>(
+        &self,
+        f: impl Fn(&T) -> U + 'static,
+    ) -> DerivationDynPtr<U> {
+        let source = self.clone();
+        DerivationPtr::new_dyn(move || f(&*source.borrow()))
+    }
+
+    /// Like `select`, but `f` borrows the selected part instead of copying it, see
+    /// `RefProjection`.
+    pub fn project<U: ?Sized>(&self, f: impl Fn(&T) -> &U + 'static) -> RefProjection<T, U> {
+        RefProjection {
+            source: self.clone(),
+            project: Rc::new(f),
+        }
+    }
+}
+
+/// A view of part of an observable's value, such as one of its fields, which can be borrowed
+/// without copying anything. This only works when the part already exists inside the observable,
+/// so unlike with `select` nothing can be computed. Since there is no separate value to compare,
+/// derivations borrowing a projection are updated whenever anything in the observable changes.
+pub struct RefProjection<T: 'static, U: ?Sized + 'static> {
+    source: ObservablePtr<T>,
+    project: Rc<dyn Fn(&T) -> &U>,
+}
+
+impl<T: 'static, U: ?Sized + 'static> Clone for RefProjection<T, U> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source.clone(),
+            project: Rc::clone(&self.project),
         }
     }
+}
+
+impl<T: 'static, U: ?Sized + 'static> RefProjection<T, U> {
+    pub fn borrow(&self) -> ObservableRef<'_, U> {
+        ObservableRef::map(self.source.borrow(), &*self.project)
+    }
 
+    pub fn borrow_untracked(&self) -> ObservableRef<'_, U> {
+        ObservableRef::map(self.source.borrow_untracked(), &*self.project)
+    }
+}
+
+impl<T: IsUnchanged + 'static> ObservablePtr<T> {
+    /// Replaces the value. If the new value is the same as the old one, nothing is notified and
+    /// the version is not bumped.
     pub fn set(&self, new_value: T) {
-        let mut value_storage = self.ptr.value.borrow_mut();
+        self.set_if_changed(new_value);
+    }
+
+    /// Like `set`, but returns whether the value changed and observers were notified. For small
+    /// `Copy` types like `f64` or `i32`, the comparison compiles down to a single instruction, so
+    /// there is no faster path to be had by comparing bits instead.
+    pub fn set_if_changed(&self, new_value: T) -> bool {
+        static_state::assert_mutation_allowed();
+        let mut value_storage = self.value_mut();
+        if value_storage.is_unchanged(&new_value) {
+            return false;
+        }
         *value_storage = new_value;
         drop(value_storage);
-        self.ptr.after_modified();
+        after_modified(Rc::clone(&self.ptr) as _, &self.ptr.version);
+        true
+    }
+
+    /// Like `set`, but returns the value that was replaced.
+    pub fn replace(&self, new_value: T) -> T {
+        static_state::assert_mutation_allowed();
+        let mut value_storage = self.value_mut();
+        let old_value = std::mem::replace(&mut *value_storage, new_value);
+        let changed = !value_storage.is_unchanged(&old_value);
+        drop(value_storage);
+        if changed {
+            after_modified(Rc::clone(&self.ptr) as _, &self.ptr.version);
+        }
+        old_value
+    }
+}
+
+impl<T: Clone + 'static> ObservablePtr<T> {
+    /// Like `borrow`, but returns a copy of the value so that no borrow is held afterwards.
+    pub fn get(&self) -> T {
+        self.borrow().clone()
+    }
+
+    /// Like `borrow_untracked`, but returns a copy of the value so that no borrow is held
+    /// afterwards.
+    pub fn get_untracked(&self) -> T {
+        self.borrow_untracked().clone()
+    }
+
+    /// Returns an untracked copy of the value which can be handed off to other threads. Must be
+    /// called from the thread observatory is bound to, see `init()`.
+    pub fn snapshot(&self) -> T
+    where
+        T: Send,
+    {
+        static_state::assert_static_state_access();
+        self.get_untracked()
     }
 }
@@ -0,0 +1,145 @@
+use crate::{static_state, ObservablePtr};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    hash::Hash,
+    rc::Rc,
+};
+
+const MIN_SWEEP_AT: usize = 16;
+
+struct ObservableMapData<K, V: 'static> {
+    /// Each key has its own observable so that derivations only depend on the keys they read.
+    /// Entries holding `None` are placeholders for keys which derivations read before they were
+    /// inserted.
+    entries: RefCell<HashMap<K, ObservablePtr<Option<V>>>>,
+    /// Once there are this many entries, placeholders which are no longer observed are removed.
+    /// Set to twice the number of remaining entries after every sweep, so that sweeping only takes
+    /// constant time per entry on average.
+    sweep_at: Cell<usize>,
+    /// Modified through `borrow_mut`, which always notifies observers, whenever a key is added or
+    /// removed.
+    key_set: ObservablePtr<()>,
+}
+
+/// A reactive `HashMap`. Reading a key with `get` only makes the current derivation depend on that
+/// key's value, so inserting or modifying other keys does not cause it to recompute. Like
+/// `ObservablePtr`, cloning this creates another pointer to the same map.
+pub struct ObservableMap<K, V: 'static> {
+    ptr: Rc<ObservableMapData<K, V>>,
+}
+
+impl<K, V: 'static> Clone for ObservableMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: Rc::clone(&self.ptr),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static, V: PartialEq + 'static> Default for ObservableMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static, V: PartialEq + 'static> ObservableMap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            ptr: Rc::new(ObservableMapData {
+                entries: RefCell::new(HashMap::new()),
+                key_set: ObservablePtr::new(()),
+                sweep_at: Cell::new(MIN_SWEEP_AT),
+            }),
+        }
+    }
+
+    fn entry(&self, key: &K) -> Option<ObservablePtr<Option<V>>> {
+        self.ptr.entries.borrow().get(key).cloned()
+    }
+
+    fn entry_or_placeholder(&self, key: &K) -> ObservablePtr<Option<V>> {
+        self.sweep_if_needed();
+        let mut entries = self.ptr.entries.borrow_mut();
+        let entry = entries
+            .entry(key.clone())
+            .or_insert_with(|| ObservablePtr::new(None));
+        ObservablePtr::clone(entry)
+    }
+
+    /// Removes placeholders nobody depends on anymore, if enough entries have piled up. Never
+    /// sweeps while a derivation is being computed, since placeholders it has read so far are not
+    /// marked as observed until it finishes.
+    fn sweep_if_needed(&self) {
+        let mut entries = self.ptr.entries.borrow_mut();
+        if entries.len() < self.ptr.sweep_at.get() || static_state::is_computing_derivation() {
+            return;
+        }
+        entries.retain(|_, entry| entry.borrow_untracked().is_some() || entry.is_observed());
+        self.ptr.sweep_at.set((entries.len() * 2).max(MIN_SWEEP_AT));
+    }
+
+    /// Inserts a value, returning the value previously stored under the same key. Derivations
+    /// which read this key are only updated if the value actually changed, but the new value is
+    /// stored either way.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let entry = self.entry_or_placeholder(&key);
+        let old = entry.replace(Some(value));
+        if old.is_none() {
+            drop(self.ptr.key_set.borrow_mut());
+        }
+        old
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let entry = self.entry(key)?;
+        if entry.borrow_untracked().is_none() {
+            return None;
+        }
+        let old = entry.borrow_mut().take();
+        if !entry.is_observed() {
+            self.ptr.entries.borrow_mut().remove(key);
+        }
+        drop(self.ptr.key_set.borrow_mut());
+        old
+    }
+
+    /// Returns a copy of the value stored under `key`. The current derivation will be updated
+    /// whenever that value changes, including when it is inserted or removed.
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        match self.entry(key) {
+            Some(entry) => entry.get(),
+            // Only derivations need a placeholder to depend on.
+            None if static_state::is_tracking() => self.entry_or_placeholder(key).get(),
+            None => None,
+        }
+    }
+
+    /// Like `get`, but does not make the current derivation depend on the value.
+    pub fn get_untracked(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.entry(key).and_then(|entry| entry.get_untracked())
+    }
+
+    /// Returns all the keys in the map, in no particular order. The current derivation will be
+    /// updated whenever a key is added or removed, but not when an existing value changes.
+    pub fn keys(&self) -> Vec<K> {
+        self.ptr.key_set.borrow();
+        self.keys_untracked()
+    }
+
+    pub fn keys_untracked(&self) -> Vec<K> {
+        self.ptr
+            .entries
+            .borrow()
+            .iter()
+            .filter(|(_, entry)| entry.borrow_untracked().is_some())
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,149 @@
+use crate::ObservablePtr;
+use std::{
+    cell::{RefCell, RefMut},
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
+
+struct ObservableVecData<T> {
+    values: RefCell<Vec<T>>,
+    /// One for each element, modified through `borrow_mut` whenever that element changes so that
+    /// derivations only depend on the indexes they read.
+    element_changed: RefCell<Vec<ObservablePtr<()>>>,
+    /// Modified through `borrow_mut` whenever elements are added or removed.
+    len_changed: ObservablePtr<()>,
+}
+
+/// A reactive `Vec`. Reading an element with `get` only makes the current derivation depend on
+/// that element, so modifying other elements in place does not cause it to recompute. Like
+/// `ObservablePtr`, cloning this creates another pointer to the same list.
+pub struct ObservableVec<T: 'static> {
+    ptr: Rc<ObservableVecData<T>>,
+}
+
+impl<T: 'static> Clone for ObservableVec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: Rc::clone(&self.ptr),
+        }
+    }
+}
+
+impl<T: 'static> Default for ObservableVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> From<Vec<T>> for ObservableVec<T> {
+    fn from(values: Vec<T>) -> Self {
+        let element_changed = values.iter().map(|_| ObservablePtr::new(())).collect();
+        Self {
+            ptr: Rc::new(ObservableVecData {
+                values: RefCell::new(values),
+                element_changed: RefCell::new(element_changed),
+                len_changed: ObservablePtr::new(()),
+            }),
+        }
+    }
+}
+
+/// Mutable access to a single element of an `ObservableVec`. Derivations which read that element
+/// are updated once this is dropped.
+pub struct ElementRefMut<'a, T> {
+    value: Option<RefMut<'a, T>>,
+    changed: ObservablePtr<()>,
+}
+
+impl<'a, T> Deref for ElementRefMut<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value.as_deref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for ElementRefMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_deref_mut().unwrap()
+    }
+}
+
+impl<'a, T> Drop for ElementRefMut<'a, T> {
+    fn drop(&mut self) {
+        // Release the element so that observers notified of the change can read it.
+        self.value = None;
+        drop(self.changed.borrow_mut());
+    }
+}
+
+impl<T: 'static> ObservableVec<T> {
+    pub fn new() -> Self {
+        Vec::new().into()
+    }
+
+    /// Returns the number of elements. The current derivation will be updated whenever elements
+    /// are added or removed.
+    pub fn len(&self) -> usize {
+        self.ptr.len_changed.borrow();
+        self.len_untracked()
+    }
+
+    pub fn len_untracked(&self) -> usize {
+        self.ptr.values.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a copy of the element at `index`. The current derivation will be updated whenever
+    /// that element changes. If there is no such element, it will instead be updated whenever
+    /// elements are added or removed.
+    pub fn get(&self, index: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        match self.ptr.element_changed.borrow().get(index) {
+            Some(changed) => changed.borrow(),
+            None => self.ptr.len_changed.borrow(),
+        };
+        self.get_untracked(index)
+    }
+
+    /// Like `get`, but does not make the current derivation depend on anything.
+    pub fn get_untracked(&self, index: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.ptr.values.borrow().get(index).cloned()
+    }
+
+    /// Allows modifying the element at `index` in place. Only derivations which read that element
+    /// are updated afterwards. Panics if there is no such element.
+    pub fn borrow_element_mut(&self, index: usize) -> ElementRefMut<'_, T> {
+        let changed = ObservablePtr::clone(&self.ptr.element_changed.borrow()[index]);
+        ElementRefMut {
+            value: Some(RefMut::map(self.ptr.values.borrow_mut(), |values| {
+                &mut values[index]
+            })),
+            changed,
+        }
+    }
+
+    /// Adds an element to the end of the list.
+    pub fn push(&self, value: T) {
+        self.ptr.values.borrow_mut().push(value);
+        let changed = ObservablePtr::new(());
+        self.ptr.element_changed.borrow_mut().push(changed);
+        drop(self.ptr.len_changed.borrow_mut());
+    }
+
+    /// Removes the last element of the list.
+    pub fn pop(&self) -> Option<T> {
+        let value = self.ptr.values.borrow_mut().pop()?;
+        let changed = self.ptr.element_changed.borrow_mut().pop().unwrap();
+        drop(changed.borrow_mut());
+        drop(self.ptr.len_changed.borrow_mut());
+        Some(value)
+    }
+}
@@ -20,7 +20,6 @@ pub(crate) trait ObserverInternalFns {
     fn send_stale(&self);
     fn send_ready(&self, changed: bool);
     fn update(&self);
-    fn get_unique_data_address(&self) -> *const ();
 }
 
 /// Helper struct which stores observers that should be notified whenever an observable object
@@ -56,13 +55,21 @@ impl ObserverList {
         self.observers.set(list);
     }
 
+    pub fn is_empty(&self) -> bool {
+        let list = self.observers.take();
+        let empty = list.is_empty();
+        self.observers.set(list);
+        empty
+    }
+
+    /// Does nothing if the observer is not in the list. Depending on the order a graph is torn
+    /// down in, a derivation being dropped may try to unsubscribe from something that already
+    /// forgot about it.
     pub fn remove(&self, observer: &Weak<dyn ObserverInternalFns>) {
         let mut list = self.observers.take();
-        let index = list.iter().position(|item| Weak::ptr_eq(item, observer));
-        let index = index.expect(
-            "(Internal error) Tried to unsubscribe an observer that was already unsubscribed.",
-        );
-        list.remove(index);
+        if let Some(index) = list.iter().position(|item| Weak::ptr_eq(item, observer)) {
+            list.remove(index);
+        }
         self.observers.set(list);
     }
 }
@@ -71,7 +78,8 @@ impl ObserverList {
 struct DerivationData<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> {
     this_ptr: Weak<dyn ObserverInternalFns>,
     observers: ObserverList,
-    observing: Cell<Vec<Rc<dyn ObservableInternalFns>>>,
+    /// Weak so that values which hold pointers to this derivation don't keep it alive forever.
+    observing: Cell<Vec<Weak<dyn ObservableInternalFns>>>,
     num_stale_notifications: Cell<usize>,
     /// True if fields we are observing have changed and we need to update once
     /// num_stale_notifications reaches zero.
@@ -113,11 +121,18 @@ impl<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> ObserverInternalFns
     fn update(&self) {
         assert!(self.should_update.get());
         self.should_update.set(false);
+        let _update = static_state::begin_update();
 
         static_state::push_observing_stack();
         let new_value = (self.compute_value.borrow_mut())();
         let now_observing = static_state::pop_observing_stack();
-        let was_observing = self.observing.take();
+        // Anything which no longer exists can't notify us anyway.
+        let was_observing: Vec<_> = self
+            .observing
+            .take()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
         for observable in &was_observing {
             let uda = observable.get_unique_data_address();
             // If we are no longer observing something we used to...
@@ -138,7 +153,8 @@ impl<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> ObserverInternalFns
                 observable.add_observer(Weak::clone(&self.this_ptr));
             }
         }
-        self.observing.set(now_observing);
+        self.observing
+            .set(now_observing.iter().map(Rc::downgrade).collect());
 
         let changed = !self.value.borrow().is_unchanged(&new_value);
         if changed {
@@ -147,16 +163,16 @@ impl<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> ObserverInternalFns
 
         self.observers.broadcast_ready(changed);
     }
-
-    fn get_unique_data_address(&self) -> *const () {
-        self.value.as_ptr() as _
-    }
 }
 
 impl<T: IsUnchanged, F: FnMut() -> T> Drop for DerivationData<T, F> {
     fn drop(&mut self) {
+        #[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+        crate::debug::node_dropped();
         for observable in self.observing.take() {
-            observable.remove_observer(&self.this_ptr);
+            if let Some(observable) = observable.upgrade() {
+                observable.remove_observer(&self.this_ptr);
+            }
         }
     }
 }
@@ -170,6 +186,14 @@ impl<T: IsUnchanged, F: FnMut() -> T> ObservableInternalFns for DerivationData<T
         self.observers.remove(observer);
     }
 
+    fn broadcast_stale(&self) {
+        self.observers.broadcast_stale();
+    }
+
+    fn broadcast_ready(&self, changed: bool) {
+        self.observers.broadcast_ready(changed);
+    }
+
     fn get_unique_data_address(&self) -> *const () {
         self.value.as_ptr() as _
     }
@@ -196,11 +220,13 @@ impl<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> DerivationPtr<T, F> {
             this_ptr: Weak::clone(weak) as _,
             num_stale_notifications: Cell::new(0),
             observers: Default::default(),
-            observing: Cell::new(observing.clone()),
+            observing: Cell::new(observing.iter().map(Rc::downgrade).collect()),
             should_update: Cell::new(false),
             compute_value: RefCell::new(compute_value),
             value: RefCell::new(initial_value),
         });
+        #[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+        crate::debug::node_created();
         let weak = &ptr.this_ptr;
         for observable in &observing {
             observable.add_observer(Weak::clone(weak) as _);
@@ -217,12 +243,51 @@ impl<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> DerivationPtr<T, F> {
         Self::new(compute_value)
     }
 
-    pub fn borrow(&self) -> Ref<T> {
+    pub fn borrow(&self) -> Ref<'_, T> {
         static_state::note_observed(Rc::clone(&self.ptr) as _);
         self.ptr.value.borrow()
     }
 
-    pub fn borrow_untracked(&self) -> Ref<T> {
+    pub fn borrow_untracked(&self) -> Ref<'_, T> {
         self.ptr.value.borrow()
     }
+
+    /// Returns true if any derivations currently depend on the value of this derivation.
+    pub fn is_observed(&self) -> bool {
+        !self.ptr.observers.is_empty()
+    }
+}
+
+/// Accessors for derivations which can fail.
+impl<V, E, F> DerivationPtr<Result<V, E>, F>
+where
+    Result<V, E>: IsUnchanged + 'static,
+    F: FnMut() -> Result<V, E> + 'static,
+{
+    /// Borrows the value if the last computation succeeded. Like `borrow`, the current derivation
+    /// depends on this one afterwards either way.
+    pub fn borrow_ok(&self) -> Option<Ref<'_, V>> {
+        let result = self.borrow();
+        if result.is_ok() {
+            Some(Ref::map(result, |result| match result {
+                Ok(value) => value,
+                Err(..) => unreachable!(),
+            }))
+        } else {
+            None
+        }
+    }
+
+    /// Borrows the error if the last computation failed, see `borrow_ok`.
+    pub fn borrow_err(&self) -> Option<Ref<'_, E>> {
+        let result = self.borrow();
+        if result.is_err() {
+            Some(Ref::map(result, |result| match result {
+                Ok(..) => unreachable!(),
+                Err(error) => error,
+            }))
+        } else {
+            None
+        }
+    }
 }
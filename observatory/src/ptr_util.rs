@@ -1,4 +1,7 @@
-use crate::{DerivationPtr, IsUnchanged, ObservablePtr};
+use crate::{
+    DerivationPtr, ExternalPtr, IsUnchanged, ObservableMap, ObservablePtr, ObservableVec,
+    PullDerivationPtr, RefProjection,
+};
 use std::{rc::Rc, sync::Arc};
 
 #[doc(hidden)]
@@ -8,25 +11,55 @@ pub trait PtrUtil {
 
 impl<T: ?Sized + IsUnchanged + 'static> PtrUtil for ObservablePtr<T> {
     fn ptr_clone(&self) -> Self {
-        Self::clone(&self)
+        Self::clone(self)
     }
 }
 
 impl<T: IsUnchanged + 'static, F: FnMut() -> T + 'static> PtrUtil for DerivationPtr<T, F> {
     fn ptr_clone(&self) -> Self {
-        Self::clone(&self)
+        Self::clone(self)
+    }
+}
+
+impl<T: 'static, F: FnMut() -> T + 'static> PtrUtil for PullDerivationPtr<T, F> {
+    fn ptr_clone(&self) -> Self {
+        Self::clone(self)
+    }
+}
+
+impl<T: 'static, U: ?Sized + 'static> PtrUtil for RefProjection<T, U> {
+    fn ptr_clone(&self) -> Self {
+        Self::clone(self)
+    }
+}
+
+impl<T: 'static> PtrUtil for ExternalPtr<T> {
+    fn ptr_clone(&self) -> Self {
+        Self::clone(self)
+    }
+}
+
+impl<K, V: 'static> PtrUtil for ObservableMap<K, V> {
+    fn ptr_clone(&self) -> Self {
+        Self::clone(self)
+    }
+}
+
+impl<T: 'static> PtrUtil for ObservableVec<T> {
+    fn ptr_clone(&self) -> Self {
+        Self::clone(self)
     }
 }
 
 impl<T: ?Sized> PtrUtil for Rc<T> {
     fn ptr_clone(&self) -> Self {
-        Rc::clone(&self)
+        Rc::clone(self)
     }
 }
 
 impl<T: ?Sized> PtrUtil for Arc<T> {
     fn ptr_clone(&self) -> Self {
-        Arc::clone(&self)
+        Arc::clone(self)
     }
 }
 
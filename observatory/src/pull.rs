@@ -0,0 +1,117 @@
+use crate::{observable::ObservableInternalFns, observer::ObserverInternalFns, static_state};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    rc::{Rc, Weak},
+};
+
+struct PullDerivationData<T: 'static, F: FnMut() -> T + 'static> {
+    this_ptr: Weak<dyn ObserverInternalFns>,
+    /// Weak so that values which hold pointers to this derivation don't keep it alive forever.
+    observing: Cell<Vec<Weak<dyn ObservableInternalFns>>>,
+    /// True if something borrowed during the last computation has changed since then.
+    stale: Cell<bool>,
+    compute_value: RefCell<F>,
+    value: RefCell<T>,
+}
+
+impl<T: 'static, F: FnMut() -> T + 'static> ObserverInternalFns for PullDerivationData<T, F> {
+    fn send_stale(&self) {}
+
+    fn send_ready(&self, changed: bool) {
+        if changed {
+            self.stale.set(true);
+        }
+    }
+
+    /// Never recomputes automatically, that only happens in `recompute_if_stale`.
+    fn update(&self) {
+        self.stale.set(true);
+    }
+}
+
+impl<T: 'static, F: FnMut() -> T + 'static> PullDerivationData<T, F> {
+    fn stop_observing(&self) {
+        for observable in self.observing.take() {
+            if let Some(observable) = observable.upgrade() {
+                observable.remove_observer(&self.this_ptr);
+            }
+        }
+    }
+}
+
+impl<T: 'static, F: FnMut() -> T + 'static> Drop for PullDerivationData<T, F> {
+    fn drop(&mut self) {
+        #[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+        crate::debug::node_dropped();
+        self.stop_observing();
+    }
+}
+
+/// A derivation which is never recomputed automatically, see `derivation_pull`.
+pub struct PullDerivationPtr<T: 'static, F: FnMut() -> T + 'static> {
+    ptr: Rc<PullDerivationData<T, F>>,
+}
+
+impl<T: 'static, F: FnMut() -> T + 'static> Clone for PullDerivationPtr<T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: Rc::clone(&self.ptr),
+        }
+    }
+}
+
+/// Creates a derivation whose dependencies are tracked like any other, but which is only
+/// recomputed when `PullDerivationPtr::recompute_if_stale` is called. Useful for expensive values
+/// which are only needed occasionally.
+pub fn derivation_pull<T: 'static, F: FnMut() -> T + 'static>(
+    mut compute_value: F,
+) -> PullDerivationPtr<T, F> {
+    static_state::push_observing_stack();
+    let initial_value = compute_value();
+    let observing = static_state::pop_observing_stack();
+    let ptr = Rc::new_cyclic(|weak| PullDerivationData {
+        this_ptr: Weak::clone(weak) as _,
+        observing: Cell::new(observing.iter().map(Rc::downgrade).collect()),
+        stale: Cell::new(false),
+        compute_value: RefCell::new(compute_value),
+        value: RefCell::new(initial_value),
+    });
+    #[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+    crate::debug::node_created();
+    for observable in &observing {
+        observable.add_observer(Weak::clone(&ptr.this_ptr));
+    }
+    PullDerivationPtr { ptr }
+}
+
+impl<T: 'static, F: FnMut() -> T + 'static> PullDerivationPtr<T, F> {
+    /// Returns the value from the last time it was computed, even if it is stale.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.ptr.value.borrow()
+    }
+
+    /// True if something borrowed during the last computation has changed since then.
+    pub fn is_stale(&self) -> bool {
+        self.ptr.stale.get()
+    }
+
+    /// Recomputes the value if it is stale, returning true if it did.
+    pub fn recompute_if_stale(&self) -> bool {
+        if !self.is_stale() {
+            return false;
+        }
+        self.ptr.stop_observing();
+        static_state::push_observing_stack();
+        let new_value = (self.ptr.compute_value.borrow_mut())();
+        let observing = static_state::pop_observing_stack();
+        for observable in &observing {
+            observable.add_observer(Weak::clone(&self.ptr.this_ptr));
+        }
+        self.ptr
+            .observing
+            .set(observing.iter().map(Rc::downgrade).collect());
+        self.ptr.value.replace(new_value);
+        self.ptr.stale.set(false);
+        true
+    }
+}
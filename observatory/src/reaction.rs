@@ -0,0 +1,116 @@
+use crate::{action, derivation_dyn, DerivationDynPtr, IsUnchanged};
+use std::{cell::RefCell, rc::Rc};
+
+/// Keeps a reaction alive. The reaction stops running as soon as its handle is dropped or
+/// disposed.
+#[must_use = "the reaction stops as soon as its handle is dropped"]
+pub struct ReactionHandle {
+    _derivation: DerivationDynPtr<()>,
+}
+
+impl ReactionHandle {
+    /// Stops the reaction. Equivalent to dropping the handle.
+    pub fn dispose(self) {}
+}
+
+/// Runs `effect` immediately and then again whenever any of the observables it borrowed change.
+pub fn autorun<F: FnMut() + 'static>(effect: F) -> ReactionHandle {
+    ReactionHandle {
+        _derivation: derivation_dyn(effect),
+    }
+}
+
+/// Runs `data`, and then again whenever any of the observables it borrowed change. Whenever the
+/// value it returns changes, `effect` is called with the new value. Unlike `autorun`, nothing
+/// borrowed by `effect` is tracked, and `effect` may modify observables. `effect` is not called for
+/// the first value returned by `data`.
+pub fn reaction<T, D, E>(data: D, mut effect: E) -> ReactionHandle
+where
+    T: IsUnchanged + Clone + 'static,
+    D: FnMut() -> T + 'static,
+    E: FnMut(T) + 'static,
+{
+    // Only notifies the effect derivation when the returned value actually changes.
+    let data = derivation_dyn(data);
+    let mut first_run = true;
+    ReactionHandle {
+        _derivation: derivation_dyn(move || {
+            let value = data.borrow().clone();
+            if first_run {
+                first_run = false;
+            } else {
+                action(|| effect(value));
+            }
+        }),
+    }
+}
+
+struct DebounceState<T> {
+    /// The latest value returned by the data function which the effect has not seen yet.
+    pending: Option<T>,
+    /// How many more ticks have to pass without changes before the effect runs.
+    remaining_ticks: usize,
+}
+
+/// Keeps a debounced reaction alive and drives it, see `reaction_debounced`.
+#[must_use = "the reaction stops as soon as its handle is dropped"]
+pub struct DebouncedReactionHandle<T> {
+    _reaction: ReactionHandle,
+    state: Rc<RefCell<DebounceState<T>>>,
+    effect: Box<dyn FnMut(T)>,
+}
+
+impl<T> DebouncedReactionHandle<T> {
+    /// Advances time by one tick, running the effect if the data has not changed for long enough.
+    pub fn tick(&mut self) {
+        let mut state = self.state.borrow_mut();
+        if state.pending.is_none() {
+            return;
+        }
+        state.remaining_ticks = state.remaining_ticks.saturating_sub(1);
+        if state.remaining_ticks == 0 {
+            let value = state.pending.take().unwrap();
+            drop(state);
+            let effect = &mut self.effect;
+            action(|| effect(value));
+        }
+    }
+
+    /// Immediately runs the effect if there is a change it has not seen yet.
+    pub fn flush(&mut self) {
+        let value = self.state.borrow_mut().pending.take();
+        if let Some(value) = value {
+            let effect = &mut self.effect;
+            action(|| effect(value));
+        }
+    }
+
+    /// Stops the reaction. Equivalent to dropping the handle.
+    pub fn dispose(self) {}
+}
+
+/// Like `reaction`, but `effect` only runs once `data` has returned the same value for `ticks`
+/// calls to `DebouncedReactionHandle::tick`. Observatory has no notion of time, so the caller
+/// decides what a tick is, e.g. a frame.
+pub fn reaction_debounced<T, D, E>(data: D, effect: E, ticks: usize) -> DebouncedReactionHandle<T>
+where
+    T: IsUnchanged + Clone + 'static,
+    D: FnMut() -> T + 'static,
+    E: FnMut(T) + 'static,
+{
+    let state = Rc::new(RefCell::new(DebounceState {
+        pending: None,
+        remaining_ticks: 0,
+    }));
+    let reaction_state = Rc::clone(&state);
+    let reaction = reaction(data, move |value| {
+        let mut state = reaction_state.borrow_mut();
+        state.pending = Some(value);
+        state.remaining_ticks = ticks;
+    });
+    DebouncedReactionHandle {
+        _reaction: reaction,
+        state,
+        effect: Box::new(effect),
+    }
+}
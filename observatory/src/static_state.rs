@@ -1,7 +1,7 @@
 use crate::observable::ObservableInternalFns;
 use crossbeam::atomic::AtomicCell;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     rc::Rc,
     thread::{self, ThreadId},
 };
@@ -10,14 +10,40 @@ use std::{
 // generate an error (and therefore prevent UB later on) we use guaranteed safe types.
 static MAIN_THREAD: AtomicCell<Option<ThreadId>> = AtomicCell::new(None);
 // Accessing these are safe as long as you first call assert_static_state_access() which checks that
-// we are on MAIN_THREAD, which never changes after an initial call to init().
+// we are on MAIN_THREAD, which never changes after it is first set by init() or by the first call to
+// assert_static_state_access().
 // https://stackoverflow.com/questions/37060330/safe-way-to-push-the-local-value-into-a-static-mut
 // static mut is safe if you are only ever accessing it from a single thread and if it is impossible
 // to hold more than one mutable reference at a time, check for reentrance!
 std::thread_local! {
-    static OBSERVING_STACK: RefCell<Vec<Vec<Rc<dyn ObservableInternalFns>>>> = RefCell::new(Vec::new());
+    static OBSERVING_STACK: RefCell<Vec<ObservingFrame>> = Default::default();
+    // How many actions are currently running, plus how many times suspend() has been called
+    // without a matching resume().
+    static BATCH_DEPTH: Cell<usize> = Default::default();
+    // How many times suspend() has been called without a matching resume().
+    static SUSPEND_DEPTH: Cell<usize> = Default::default();
+    // Modified observables which have broadcast stale but not ready because an action is running
+    // or propagation is suspended.
+    static PENDING_READY: RefCell<Vec<Rc<dyn ObservableInternalFns>>> = Default::default();
+    // How many derivation updates are currently running inside each other.
+    static UPDATE_DEPTH: Cell<usize> = Default::default();
+    // None until set_max_update_depth() is called, in which case DEFAULT_MAX_UPDATE_DEPTH is used.
+    static MAX_UPDATE_DEPTH: Cell<Option<usize>> = Default::default();
 }
 
+const DEFAULT_MAX_UPDATE_DEPTH: usize = 1000;
+
+enum ObservingFrame {
+    /// Observables borrowed while this frame is on top are recorded as dependencies.
+    Tracking(Vec<Rc<dyn ObservableInternalFns>>),
+    /// Observables borrowed while this frame is on top are not recorded anywhere.
+    Untracked,
+    /// Like Untracked, but observables may also be modified even if a derivation is being computed.
+    Action,
+}
+
+/// Binds observatory to the current thread. Calling this is optional, the first reactive operation
+/// binds to whichever thread it runs on. Panics if observatory is already bound to a thread.
 pub fn init() {
     if MAIN_THREAD.load().is_some() {
         panic!("Called init() a second time.");
@@ -29,48 +55,249 @@ pub fn is_initialized() -> bool {
     MAIN_THREAD.load().is_some()
 }
 
-/// Panics if init() has not been called or if called from a different thread than init() was called
-/// on.
-fn assert_static_state_access() {
+/// Panics if called from a different thread than observatory is bound to. If it is not bound to
+/// any thread yet, binds it to the current one.
+pub(crate) fn assert_static_state_access() {
     let this_thread = Some(thread::current().id());
-    let mt = MAIN_THREAD.load();
+    // Leaves MAIN_THREAD alone and returns its value if it was already set.
+    let mt = match MAIN_THREAD.compare_exchange(None, this_thread) {
+        Ok(..) => this_thread,
+        Err(mt) => mt,
+    };
     if mt != this_thread {
-        if let Some(id) = mt {
-            panic!(
-                concat!(
-                    "a function was just called from thread {:?} ",
-                    "but observatory::init() was called from thread {:?}"
-                ),
-                thread::current().id(),
-                id
-            );
-        } else {
-            panic!(concat!(
-                "Function called before initialization, ",
-                "are you missing a call to observatory::init()?"
-            ));
-        }
+        panic!(
+            concat!(
+                "a function was just called from thread {:?} ",
+                "but observatory is bound to thread {:?}"
+            ),
+            thread::current().id(),
+            mt.unwrap()
+        );
     }
 }
 
 pub(crate) fn push_observing_stack() {
     assert_static_state_access();
-    OBSERVING_STACK.with(|stack| stack.borrow_mut().push(Vec::new()));
+    OBSERVING_STACK.with(|stack| {
+        stack
+            .borrow_mut()
+            .push(ObservingFrame::Tracking(Vec::new()))
+    });
 }
 
-pub(crate) fn note_observed(observable: Rc<dyn ObservableInternalFns>) {
+/// Pops the frame it was created for when dropped, even if the code running inside of that frame
+/// panicked.
+#[must_use]
+pub(crate) struct UntrackedGuard {
+    /// How many frames were on the stack before this one was pushed.
+    depth: usize,
+}
+
+impl Drop for UntrackedGuard {
+    fn drop(&mut self) {
+        let top = pop_frames_down_to(self.depth);
+        if !thread::panicking() && !matches!(top, Some(ObservingFrame::Untracked)) {
+            panic!("(Internal error) untracked frame was not on top of the observing stack");
+        }
+    }
+}
+
+pub(crate) fn push_untracked() -> UntrackedGuard {
     assert_static_state_access();
+    let depth = OBSERVING_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        stack.push(ObservingFrame::Untracked);
+        stack.len() - 1
+    });
+    UntrackedGuard { depth }
+}
+
+/// Pops the frame which was pushed when the stack was `depth` frames tall. If something panicked
+/// while that frame was on top, frames pushed after it might not have been popped yet, so those
+/// are discarded as well.
+fn pop_frames_down_to(depth: usize) -> Option<ObservingFrame> {
     OBSERVING_STACK.with(|stack| {
         let mut stack = stack.borrow_mut();
-        if let Some(item) = stack.last_mut() {
-            let uda = observable.get_unique_data_address();
-            if !item.iter().any(|item| item.get_unique_data_address() == uda) {
-                item.push(observable);
+        if thread::panicking() {
+            stack.truncate(depth + 1);
+        }
+        stack.pop()
+    })
+}
+
+/// True if observables borrowed right now would be recorded as dependencies of a derivation.
+pub(crate) fn is_tracking() -> bool {
+    OBSERVING_STACK
+        .with(|stack| matches!(stack.borrow().last(), Some(ObservingFrame::Tracking(..))))
+}
+
+/// True if a derivation's value is being computed, even if it is not tracking dependencies right
+/// now because of `untracked` or `action`.
+pub(crate) fn is_computing_derivation() -> bool {
+    OBSERVING_STACK.with(|stack| {
+        (stack.borrow().iter()).any(|frame| matches!(frame, ObservingFrame::Tracking(..)))
+    })
+}
+
+/// Panics if called while a derivation's value is being computed, since derivations must not have
+/// side effects.
+pub(crate) fn assert_mutation_allowed() {
+    // This does not go through assert_static_state_access() so that modifying an observable does
+    // not bind observatory to a thread by itself, in which case the stack is just empty.
+    OBSERVING_STACK.with(|stack| {
+        let stack = stack.borrow();
+        for frame in stack.iter().rev() {
+            match frame {
+                ObservingFrame::Tracking(..) => panic!(concat!(
+                    "Observable modified while computing a derivation. ",
+                    "Derivations must not have side effects, ",
+                    "use observatory::action() if this is intentional."
+                )),
+                ObservingFrame::Untracked => (),
+                ObservingFrame::Action => return,
             }
-        } else {
-            panic!(
-            "Observable borrowed outside of derivation. Did you mean to use borrow_untracked()?"
+        }
+    });
+}
+
+/// Sets how many derivations may be updated inside of each other, which happens when each one
+/// depends on the one updated before it. Exceeding this panics instead of overflowing the stack.
+/// Defaults to 1000.
+pub fn set_max_update_depth(depth: usize) {
+    assert_static_state_access();
+    MAX_UPDATE_DEPTH.with(|max| max.set(Some(depth)));
+}
+
+/// The limit set by `set_max_update_depth`.
+pub fn max_update_depth() -> usize {
+    MAX_UPDATE_DEPTH
+        .with(Cell::get)
+        .unwrap_or(DEFAULT_MAX_UPDATE_DEPTH)
+}
+
+/// Counts the derivation it was created for as no longer updating when dropped, even if the
+/// update panicked. In that case, observing frames the update pushed are discarded as well.
+#[must_use]
+pub(crate) struct UpdateGuard {
+    /// How many frames were on the observing stack when the update began.
+    stack_depth: usize,
+}
+
+impl Drop for UpdateGuard {
+    fn drop(&mut self) {
+        UPDATE_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        if thread::panicking() {
+            OBSERVING_STACK.with(|stack| stack.borrow_mut().truncate(self.stack_depth));
+        }
+    }
+}
+
+/// Counts a derivation as updating until the returned guard is dropped.
+pub(crate) fn begin_update() -> UpdateGuard {
+    let depth = UPDATE_DEPTH.with(Cell::get) + 1;
+    let max = max_update_depth();
+    if depth > max {
+        panic!(
+            concat!(
+                "More than {} derivations were updated inside each other. Either a chain of ",
+                "derivations is very long, in which case observatory::set_max_update_depth() ",
+                "can raise the limit, or derivations accidentally depend on each other in a cycle."
+            ),
+            max
         );
+    }
+    UPDATE_DEPTH.with(|current| current.set(depth));
+    UpdateGuard {
+        stack_depth: OBSERVING_STACK.with(|stack| stack.borrow().len()),
+    }
+}
+
+/// Ends the action it was created for when dropped, even if the action panicked. Deferred ready
+/// notifications are still sent in that case.
+#[must_use]
+pub(crate) struct ActionGuard {
+    /// How many frames were on the stack before the action's frame was pushed.
+    depth: usize,
+}
+
+impl Drop for ActionGuard {
+    fn drop(&mut self) {
+        let top = pop_frames_down_to(self.depth);
+        if !thread::panicking() && !matches!(top, Some(ObservingFrame::Action)) {
+            panic!("(Internal error) action frame was not on top of the observing stack");
+        }
+        end_batch();
+    }
+}
+
+pub(crate) fn begin_action() -> ActionGuard {
+    assert_static_state_access();
+    let depth = OBSERVING_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        stack.push(ObservingFrame::Action);
+        stack.len() - 1
+    });
+    BATCH_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    ActionGuard { depth }
+}
+
+pub(crate) fn suspend() {
+    assert_static_state_access();
+    SUSPEND_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    BATCH_DEPTH.with(|depth| depth.set(depth.get() + 1));
+}
+
+pub(crate) fn resume() {
+    assert_static_state_access();
+    SUSPEND_DEPTH.with(|depth| {
+        if depth.get() == 0 {
+            panic!("resume() called without a matching suspend()");
+        }
+        depth.set(depth.get() - 1);
+    });
+    end_batch();
+}
+
+/// Once nothing is batching anymore, sends all the ready notifications deferred in the meantime.
+fn end_batch() {
+    let depth = BATCH_DEPTH.with(|depth| {
+        depth.set(depth.get() - 1);
+        depth.get()
+    });
+    if depth == 0 {
+        let pending = PENDING_READY.with(|pending| pending.take());
+        for observable in pending {
+            observable.broadcast_ready(true);
+        }
+    }
+}
+
+pub(crate) fn is_batching() -> bool {
+    BATCH_DEPTH.with(|depth| depth.get() > 0)
+}
+
+pub(crate) fn defer_ready(observable: Rc<dyn ObservableInternalFns>) {
+    PENDING_READY.with(|pending| pending.borrow_mut().push(observable));
+}
+
+pub(crate) fn note_observed(observable: Rc<dyn ObservableInternalFns>) {
+    assert_static_state_access();
+    OBSERVING_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        match stack.last_mut() {
+            Some(ObservingFrame::Tracking(item)) => {
+                let uda = observable.get_unique_data_address();
+                if !item
+                    .iter()
+                    .any(|item| item.get_unique_data_address() == uda)
+                {
+                    item.push(observable);
+                }
+            }
+            Some(ObservingFrame::Untracked) | Some(ObservingFrame::Action) => (),
+            None => panic!(
+                "Observable borrowed outside of derivation. Did you mean to use borrow_untracked()?"
+            ),
         }
     });
 }
@@ -78,7 +305,7 @@ pub(crate) fn note_observed(observable: Rc<dyn ObservableInternalFns>) {
 pub(crate) fn pop_observing_stack() -> Vec<Rc<dyn ObservableInternalFns>> {
     assert_static_state_access();
     let top = OBSERVING_STACK.with(|stack| stack.borrow_mut().pop());
-    if let Some(value) = top {
+    if let Some(ObservingFrame::Tracking(value)) = top {
         value
     } else {
         panic!("(Internal error) pop() called more times than push()");
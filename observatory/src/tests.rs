@@ -1,7 +1,11 @@
 #![cfg(test)]
 
 use crate::*;
-use std::{cell::Cell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+};
 
 fn init_if_needed() {
     if !is_initialized() {
@@ -76,7 +80,7 @@ fn update_only_once() {
         intermediates
             .iter()
             .map(|value| *value.borrow())
-            .fold(0, std::ops::Add::add)
+            .sum::<i32>()
     });
 
     assert_eq!(num_updates2.get(), 1);
@@ -176,3 +180,874 @@ fn ptr_clone_macro() {
     holder.value.set(42);
     assert_eq!(*derived.borrow_untracked(), 42);
 }
+
+#[test]
+fn is_observed_follows_subscriptions() {
+    init_if_needed();
+    let value = observable(1);
+    let deriveda = derivation_with_ptrs!(value; *value.borrow() + 1);
+    assert!(value.is_observed());
+    assert!(!deriveda.is_observed());
+    let derivedb = derivation_with_ptrs!(deriveda; *deriveda.borrow() + 1);
+    assert!(deriveda.is_observed());
+    assert!(!derivedb.is_observed());
+    drop(derivedb);
+    assert!(!deriveda.is_observed());
+    drop(deriveda);
+    assert!(!value.is_observed());
+}
+
+#[test]
+fn dropped_reaction_stops_running() {
+    init_if_needed();
+    let value = observable(0);
+    let num_runs = Rc::new(Cell::new(0));
+    let handle = {
+        ptr_clone!(value, num_runs);
+        autorun(move || {
+            value.borrow();
+            num_runs.set(num_runs.get() + 1);
+        })
+    };
+    assert_eq!(num_runs.get(), 1);
+    value.set(1);
+    assert_eq!(num_runs.get(), 2);
+    drop(handle);
+    value.set(2);
+    value.set(3);
+    assert_eq!(num_runs.get(), 2);
+    assert!(!value.is_observed());
+}
+
+#[test]
+fn disposed_reaction_stops_running() {
+    init_if_needed();
+    let value = observable(0);
+    let num_runs = Rc::new(Cell::new(0));
+    let handle = {
+        ptr_clone!(value, num_runs);
+        autorun(move || {
+            value.borrow();
+            num_runs.set(num_runs.get() + 1);
+        })
+    };
+    handle.dispose();
+    value.set(1);
+    assert_eq!(num_runs.get(), 1);
+}
+
+#[test]
+fn untracked_borrow_does_not_subscribe() {
+    init_if_needed();
+    let tracked = observable(1);
+    let config = observable(10);
+    let num_updates = Rc::new(Cell::new(0));
+    let result = derivation_with_ptrs!(tracked, config, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *tracked.borrow() * untracked(|| *config.borrow())
+    });
+    assert_eq!(*result.borrow_untracked(), 10);
+    config.set(20);
+    assert_eq!(num_updates.get(), 1);
+    assert_eq!(*result.borrow_untracked(), 10);
+    assert!(!config.is_observed());
+    tracked.set(2);
+    assert_eq!(num_updates.get(), 2);
+    assert_eq!(*result.borrow_untracked(), 40);
+}
+
+#[test]
+#[should_panic(expected = "Observable modified while computing a derivation.")]
+fn set_inside_derivation_panics() {
+    init_if_needed();
+    let source = observable(1);
+    let target = observable(0);
+    let _derived = derivation_with_ptrs!(source, target; target.set(*source.borrow()));
+}
+
+#[test]
+fn action_batches_updates() {
+    init_if_needed();
+    let a = observable(1);
+    let b = observable(2);
+    let num_updates = Rc::new(Cell::new(0));
+    let sum = derivation_with_ptrs!(a, b, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *a.borrow() + *b.borrow()
+    });
+    action(|| {
+        a.set(10);
+        b.set(20);
+        // Updates are deferred until the action is complete.
+        assert_eq!(*sum.borrow_untracked(), 3);
+    });
+    assert_eq!(num_updates.get(), 2);
+    assert_eq!(*sum.borrow_untracked(), 30);
+}
+
+#[test]
+fn action_allows_set_inside_derivation() {
+    init_if_needed();
+    let source = observable(1);
+    let target = observable(0);
+    let _derived = derivation_with_ptrs!(source, target; {
+        let value = *source.borrow();
+        action(|| target.set(value));
+    });
+    assert_eq!(*target.borrow_untracked(), 1);
+    source.set(5);
+    assert_eq!(*target.borrow_untracked(), 5);
+}
+
+#[test]
+fn get_does_not_hold_borrow() {
+    init_if_needed();
+    let value = observable(1);
+    let doubled = derivation_with_ptrs!(value; value.get() * 2);
+    for _ in 0..3 {
+        let old = value.get_untracked();
+        value.set(old + 1);
+    }
+    assert_eq!(value.get_untracked(), 4);
+    assert_eq!(*doubled.borrow_untracked(), 8);
+}
+
+/// Increments a counter when dropped.
+struct DropCounter(Rc<Cell<usize>>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+impl PartialEq for DropCounter {
+    fn eq(&self, _other: &Self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn cyclic_graph_is_freed() {
+    init_if_needed();
+    let num_drops = Rc::new(Cell::new(0));
+    let slot = observable::<Option<DerivationDynPtr<DropCounter>>>(None);
+    // The derivation only reaches the slot through this, so that once it is cleared the only
+    // thing linking the derivation to the slot is the derivation's list of what it observes.
+    let route = Rc::new(RefCell::new(Some(ObservablePtr::clone(&slot))));
+    let derived = derivation_with_ptrs_dyn!(route, num_drops; {
+        if let Some(slot) = &*route.borrow() {
+            slot.borrow();
+        }
+        DropCounter(Rc::clone(&num_drops))
+    });
+    // The slot now holds the derivation, which observes the slot.
+    *slot.borrow_mut() = Some(DerivationPtr::clone(&derived));
+    assert_eq!(num_drops.get(), 1);
+    route.borrow_mut().take();
+    drop(slot);
+    drop(derived);
+    assert_eq!(num_drops.get(), 2);
+}
+
+#[test]
+fn selection_ignores_other_fields() {
+    init_if_needed();
+    struct Pair {
+        a: i32,
+        b: i32,
+    }
+    let pair = observable(Pair { a: 1, b: 2 });
+    let a = pair.select(|pair| pair.a);
+    let num_updates = Rc::new(Cell::new(0));
+    let doubled = derivation_with_ptrs!(a, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *a.borrow() * 2
+    });
+    pair.borrow_mut().b = 20;
+    assert_eq!(num_updates.get(), 1);
+    pair.borrow_mut().a = 10;
+    assert_eq!(num_updates.get(), 2);
+    assert_eq!(*doubled.borrow_untracked(), 20);
+    assert_eq!(pair.borrow_untracked().b, 20);
+}
+
+#[test]
+fn snapshot_sent_to_other_thread() {
+    init_if_needed();
+    let value = observable(vec![1, 2, 3]);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let worker = std::thread::spawn(move || {
+        let data: Vec<i32> = receiver.recv().unwrap();
+        data.iter().sum::<i32>()
+    });
+    sender.send(value.snapshot()).unwrap();
+    value.set(vec![]);
+    assert_eq!(worker.join().unwrap(), 6);
+}
+
+#[test]
+fn map_key_read_ignores_other_keys() {
+    init_if_needed();
+    let map = ObservableMap::new();
+    map.insert("a", 1);
+    let num_updates = Rc::new(Cell::new(0));
+    let a = derivation_with_ptrs!(map, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        map.get(&"a")
+    });
+    let keys = derivation_with_ptrs!(map; {
+        let mut keys = map.keys();
+        keys.sort_unstable();
+        keys
+    });
+    map.insert("b", 2);
+    assert_eq!(num_updates.get(), 1);
+    assert_eq!(*keys.borrow_untracked(), vec!["a", "b"]);
+    map.insert("a", 1);
+    assert_eq!(num_updates.get(), 1);
+    map.insert("a", 3);
+    assert_eq!(num_updates.get(), 2);
+    assert_eq!(*a.borrow_untracked(), Some(3));
+    assert_eq!(map.remove(&"a"), Some(3));
+    assert_eq!(*a.borrow_untracked(), None);
+    assert_eq!(*keys.borrow_untracked(), vec!["b"]);
+}
+
+#[test]
+fn map_insert_equal_returns_stored_value() {
+    init_if_needed();
+    let map = ObservableMap::new();
+    map.insert("zero", 0.0_f64);
+    let num_updates = Rc::new(Cell::new(0));
+    let _zero = derivation_with_ptrs!(map, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        map.get(&"zero")
+    });
+    let old = map.insert("zero", -0.0).unwrap();
+    assert!(old.is_sign_positive());
+    assert!(untracked(|| map.get(&"zero")).unwrap().is_sign_negative());
+    assert_eq!(num_updates.get(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Observable modified while it is still borrowed")]
+fn set_while_borrowed_panics() {
+    init_if_needed();
+    let value = observable(1);
+    let _derived = derivation_with_ptrs!(value; {
+        let current = value.borrow();
+        action(|| value.set(*current + 1));
+    });
+}
+
+#[test]
+fn version_bumps_on_real_changes() {
+    init_if_needed();
+    let value = observable(1);
+    let num_updates = Rc::new(Cell::new(0));
+    let _derived = derivation_with_ptrs!(value, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *value.borrow()
+    });
+    let initial = value.version();
+    value.set(2);
+    let after_change = value.version();
+    assert!(after_change > initial);
+    value.set(2);
+    assert_eq!(value.version(), after_change);
+    assert_eq!(num_updates.get(), 2);
+    *value.borrow_mut() += 1;
+    assert!(value.version() > after_change);
+}
+
+#[test]
+fn invalidated_external_updates_dependents() {
+    init_if_needed();
+    let source = Rc::new(Cell::new(1));
+    let source2 = Rc::clone(&source);
+    let external = observable_external(move || source2.get());
+    let doubled = derivation_with_ptrs!(external; *external.borrow() * 2);
+    source.set(5);
+    assert_eq!(*doubled.borrow_untracked(), 2);
+    external.invalidate();
+    assert_eq!(*doubled.borrow_untracked(), 10);
+}
+
+#[test]
+fn replace_returns_old_value() {
+    init_if_needed();
+    let state = observable("idle");
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let _handle = {
+        ptr_clone!(state, seen);
+        autorun(move || seen.borrow_mut().push(*state.borrow()))
+    };
+    assert_eq!(state.replace("running"), "idle");
+    assert_eq!(state.replace("running"), "running");
+    assert_eq!(*seen.borrow(), vec!["idle", "running"]);
+}
+
+#[test]
+fn combined_observables_update() {
+    init_if_needed();
+    let a = observable(1);
+    let b = observable(2);
+    let c = observable(3);
+    let sum = combine2(&a, &b, |a, b| a + b);
+    let product = combine3(&a, &b, &c, |a, b, c| a * b * c);
+    assert_eq!(*sum.borrow_untracked(), 3);
+    a.set(10);
+    assert_eq!(*sum.borrow_untracked(), 12);
+    b.set(20);
+    assert_eq!(*sum.borrow_untracked(), 30);
+    c.set(2);
+    assert_eq!(*product.borrow_untracked(), 400);
+}
+
+#[test]
+fn format_derivation_macro() {
+    init_if_needed();
+    let first_name = observable("William");
+    let last_name = observable("Riker");
+    let display_name = format_derivation!("{} {}", first_name, last_name);
+    assert_eq!(*display_name.borrow_untracked(), "William Riker");
+    first_name.set("Will of Yam");
+    assert_eq!(*display_name.borrow_untracked(), "Will of Yam Riker");
+}
+
+#[test]
+fn set_unchecked_always_notifies() {
+    init_if_needed();
+    let value = observable(1);
+    let num_updates = Rc::new(Cell::new(0));
+    let _derived = derivation_with_ptrs!(value, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *value.borrow()
+    });
+    value.set(1);
+    assert_eq!(num_updates.get(), 1);
+    value.set_unchecked(1);
+    assert_eq!(num_updates.get(), 2);
+}
+
+#[test]
+fn drop_chained_graph_in_any_order() {
+    init_if_needed();
+    for order in &[[0, 1, 2, 3], [3, 2, 1, 0], [1, 3, 0, 2], [2, 0, 3, 1]] {
+        let source = observable(1);
+        let a = derivation_with_ptrs_dyn!(source; *source.borrow() + 1);
+        let b = derivation_with_ptrs_dyn!(a; *a.borrow() + 1);
+        let c =
+            derivation_with_ptrs_dyn!(source, a, b; *source.borrow() + *a.borrow() + *b.borrow());
+        let mut derivations = [Some(a), Some(b), Some(c)];
+        source.set(2);
+        let mut source = Some(source);
+        for &index in order {
+            if index == 3 {
+                source.take();
+            } else {
+                derivations[index].take();
+            }
+        }
+    }
+}
+
+#[test]
+fn suspended_updates_settle_once() {
+    init_if_needed();
+    let value = observable(0);
+    let left = derivation_with_ptrs!(value; *value.borrow() + 1);
+    let right = derivation_with_ptrs!(value; *value.borrow() * 2);
+    let num_updates = Rc::new(Cell::new(0));
+    let joined = derivation_with_ptrs!(left, right, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *left.borrow() + *right.borrow()
+    });
+    suspend();
+    for index in 1..=10 {
+        value.set(index);
+    }
+    assert_eq!(num_updates.get(), 1);
+    suspended(|| value.set(20));
+    assert_eq!(num_updates.get(), 1);
+    resume();
+    assert_eq!(num_updates.get(), 2);
+    assert_eq!(*joined.borrow_untracked(), 61);
+}
+
+#[test]
+#[should_panic(expected = "resume() called without a matching suspend()")]
+fn unmatched_resume_panics() {
+    init_if_needed();
+    resume();
+}
+
+#[test]
+fn reaction_effect_is_untracked() {
+    init_if_needed();
+    let tracked = observable(1);
+    let read_in_effect = observable(10);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let _handle = {
+        ptr_clone!(tracked, read_in_effect, seen);
+        reaction(
+            move || *tracked.borrow() / 2,
+            move |value| seen.borrow_mut().push(value + *read_in_effect.borrow()),
+        )
+    };
+    assert!(seen.borrow().is_empty());
+    tracked.set(2);
+    assert_eq!(*seen.borrow(), vec![11]);
+    tracked.set(3);
+    assert_eq!(*seen.borrow(), vec![11]);
+    read_in_effect.set(20);
+    assert_eq!(*seen.borrow(), vec![11]);
+    assert!(!read_in_effect.is_observed());
+    tracked.set(4);
+    assert_eq!(*seen.borrow(), vec![11, 22]);
+}
+
+#[test]
+fn weak_observable_upgrades_while_alive() {
+    let value = observable(5);
+    let weak = value.downgrade();
+    let value2 = weak.upgrade().unwrap();
+    assert_eq!(*value2.borrow_untracked(), 5);
+    drop(value);
+    assert!(weak.upgrade().is_some());
+    drop(value2);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+fn dropped_observable_is_released() {
+    let baseline = debug::live_node_count();
+    let value = observable(5);
+    let weak = value.downgrade();
+    assert_eq!(debug::live_node_count(), baseline + 1);
+    drop(value);
+    assert!(weak.upgrade().is_none());
+    assert_eq!(debug::live_node_count(), baseline);
+}
+
+#[test]
+fn debounced_reaction_waits_for_quiet() {
+    init_if_needed();
+    let position = observable(0);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let mut handle = {
+        ptr_clone!(position, seen);
+        reaction_debounced(
+            move || *position.borrow(),
+            move |value| seen.borrow_mut().push(value),
+            2,
+        )
+    };
+    for value in 1..=5 {
+        position.set(value);
+        handle.tick();
+    }
+    assert!(seen.borrow().is_empty());
+    handle.tick();
+    assert_eq!(*seen.borrow(), vec![5]);
+    handle.tick();
+    handle.tick();
+    assert_eq!(*seen.borrow(), vec![5]);
+    position.set(6);
+    position.set(7);
+    handle.flush();
+    assert_eq!(*seen.borrow(), vec![5, 7]);
+}
+
+#[test]
+fn keyed_memo_only_recomputes_changed_item() {
+    init_if_needed();
+    let items: Vec<_> = (0..3).map(|id| observable((id, id * 10))).collect();
+    let computed = Rc::new(RefCell::new(Vec::new()));
+    let mut memo = {
+        ptr_clone!(computed);
+        keyed_memo(
+            |item: &ObservablePtr<(i32, i32)>| item.borrow_untracked().0,
+            move |item| {
+                let (id, value) = *item.borrow();
+                computed.borrow_mut().push(id);
+                value + 1
+            },
+        )
+    };
+    let values: Vec<_> = items.iter().map(|item| memo.get(item)).collect();
+    assert_eq!(*computed.borrow(), vec![0, 1, 2]);
+    computed.borrow_mut().clear();
+    items[1].set((1, 50));
+    assert_eq!(*computed.borrow(), vec![1]);
+    assert_eq!(*values[1].borrow_untracked(), 51);
+    assert_eq!(*memo.get(&items[1]).borrow_untracked(), 51);
+    assert_eq!(*computed.borrow(), vec![1]);
+    memo.retain(&items[..2]);
+    assert_eq!(memo.len(), 2);
+}
+
+#[test]
+fn fallible_derivation_accessors() {
+    init_if_needed();
+    let input = observable("x");
+    let parsed = {
+        ptr_clone!(input);
+        derivation(move || input.borrow().parse::<i32>().map_err(|_| "not a number"))
+    };
+    untracked(|| {
+        assert!(parsed.borrow_ok().is_none());
+        assert_eq!(parsed.borrow_err().as_deref(), Some(&"not a number"));
+    });
+    input.set("12");
+    untracked(|| {
+        assert_eq!(parsed.borrow_ok().as_deref(), Some(&12));
+        assert!(parsed.borrow_err().is_none());
+    });
+}
+
+#[test]
+fn first_use_initializes() {
+    // Deliberately not calling init_if_needed().
+    let value = observable(1);
+    let doubled = {
+        ptr_clone!(value);
+        derivation(move || *value.borrow() * 2)
+    };
+    assert!(is_initialized());
+    value.set(2);
+    assert_eq!(*doubled.borrow_untracked(), 4);
+}
+
+#[test]
+fn diff_pure_insert() {
+    assert_eq!(
+        diff_keyed(&["a", "c"], &["a", "b", "c", "d"]),
+        vec![
+            DiffOp::Insert { index: 1, key: "b" },
+            DiffOp::Insert { index: 3, key: "d" },
+        ]
+    );
+}
+
+#[test]
+fn diff_pure_remove() {
+    assert_eq!(
+        diff_keyed(&["a", "b", "c"], &["b"]),
+        vec![
+            DiffOp::Remove { index: 0, key: "a" },
+            DiffOp::Remove { index: 2, key: "c" },
+        ]
+    );
+}
+
+#[test]
+fn diff_reorder() {
+    assert_eq!(
+        diff_keyed(&["a", "b", "c", "d"], &["d", "a", "b", "c"]),
+        vec![DiffOp::Move {
+            from: 3,
+            to: 0,
+            key: "d"
+        }]
+    );
+    assert!(diff_keyed(&[1, 2, 3], &[1, 2, 3]).is_empty());
+}
+
+#[test]
+fn vec_element_mutation_is_isolated() {
+    init_if_needed();
+    let list = ObservableVec::from(vec![1, 2]);
+    let computations = Rc::new(Cell::new(0));
+    let second = {
+        ptr_clone!(list, computations);
+        derivation(move || {
+            computations.set(computations.get() + 1);
+            list.get(1)
+        })
+    };
+    *list.borrow_element_mut(0) += 10;
+    assert_eq!(computations.get(), 1);
+    assert_eq!(list.get_untracked(0), Some(11));
+    *list.borrow_element_mut(1) += 10;
+    assert_eq!(computations.get(), 2);
+    assert_eq!(*second.borrow_untracked(), Some(12));
+    list.pop();
+    assert_eq!(*second.borrow_untracked(), None);
+    list.push(5);
+    assert_eq!(*second.borrow_untracked(), Some(5));
+}
+
+#[test]
+fn pull_derivation_waits_for_request() {
+    init_if_needed();
+    let input = observable(2);
+    let computations = Rc::new(Cell::new(0));
+    let squared = {
+        ptr_clone!(input, computations);
+        derivation_pull(move || {
+            computations.set(computations.get() + 1);
+            *input.borrow() * *input.borrow()
+        })
+    };
+    input.set(3);
+    input.set(4);
+    assert_eq!(computations.get(), 1);
+    assert_eq!(*squared.borrow(), 4);
+    assert!(squared.recompute_if_stale());
+    assert!(!squared.recompute_if_stale());
+    assert_eq!(computations.get(), 2);
+    assert_eq!(*squared.borrow(), 16);
+    input.set(5);
+    assert!(squared.is_stale());
+}
+
+#[test]
+fn boolean_combinators() {
+    init_if_needed();
+    let (a, b) = (observable(false), observable(true));
+    let both = all(&[a.clone(), b.clone()]);
+    let either = any(&[a.clone(), b.clone()]);
+    let not_a = not(&a);
+    assert_eq!(
+        (*both.borrow_untracked(), *either.borrow_untracked()),
+        (false, true)
+    );
+    assert!(*not_a.borrow_untracked());
+    a.set(true);
+    assert_eq!(
+        (*both.borrow_untracked(), *either.borrow_untracked()),
+        (true, true)
+    );
+    assert!(!*not_a.borrow_untracked());
+    b.set(false);
+    assert!(!*both.borrow_untracked());
+    a.set(false);
+    assert!(!*either.borrow_untracked());
+}
+
+fn derivation_chain(root: &ObservablePtr<i32>, length: usize) -> DerivationDynPtr<i32> {
+    let root = root.clone();
+    let mut last = derivation_dyn(move || *root.borrow());
+    for _ in 1..length {
+        let previous = last.clone();
+        last = derivation_dyn(move || *previous.borrow() + 1);
+    }
+    last
+}
+
+/// Changes the maximum update depth until dropped, even if the test panics.
+struct MaxUpdateDepth(usize);
+
+impl MaxUpdateDepth {
+    fn set(depth: usize) -> Self {
+        let previous = max_update_depth();
+        set_max_update_depth(depth);
+        Self(previous)
+    }
+}
+
+impl Drop for MaxUpdateDepth {
+    fn drop(&mut self) {
+        set_max_update_depth(self.0);
+    }
+}
+
+#[test]
+fn chain_within_update_depth() {
+    init_if_needed();
+    let _limit = MaxUpdateDepth::set(100);
+    let root = observable(0);
+    let end = derivation_chain(&root, 100);
+    root.set(1);
+    assert_eq!(*end.borrow_untracked(), 100);
+}
+
+#[test]
+#[should_panic(expected = "More than 100 derivations were updated inside each other")]
+fn chain_exceeding_update_depth() {
+    init_if_needed();
+    let _limit = MaxUpdateDepth::set(100);
+    let root = observable(0);
+    let _end = derivation_chain(&root, 101);
+    root.set(1);
+}
+
+#[test]
+fn observable_with_computes_once() {
+    init_if_needed();
+    let calls = Cell::new(0);
+    let value = observable_with(|| {
+        calls.set(calls.get() + 1);
+        vec![1, 2, 3]
+    });
+    assert_eq!(calls.get(), 1);
+    let len = {
+        ptr_clone!(value);
+        derivation(move || value.borrow().len())
+    };
+    value.borrow_mut().push(4);
+    assert_eq!(*len.borrow_untracked(), 4);
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+fn dropped_graph_has_no_live_nodes() {
+    init_if_needed();
+    let baseline = debug::live_node_count();
+    {
+        let root = observable(1);
+        let end = derivation_chain(&root, 10);
+        root.set(2);
+        assert_eq!(*end.borrow_untracked(), 11);
+        assert_eq!(debug::live_node_count(), baseline + 11);
+    }
+    assert_eq!(debug::live_node_count(), baseline);
+}
+
+struct CountedClone {
+    name: String,
+    clones: Rc<Cell<usize>>,
+}
+
+impl Clone for CountedClone {
+    fn clone(&self) -> Self {
+        self.clones.set(self.clones.get() + 1);
+        Self {
+            name: self.name.clone(),
+            clones: Rc::clone(&self.clones),
+        }
+    }
+}
+
+#[test]
+fn projection_borrows_without_cloning() {
+    init_if_needed();
+    let clones = Rc::new(Cell::new(0));
+    let source = observable(CountedClone {
+        name: "Data".to_owned(),
+        clones: Rc::clone(&clones),
+    });
+    let name = derivation_ref(&source, |value| value.name.as_str());
+    let name_len = {
+        ptr_clone!(name);
+        derivation(move || name.borrow().len())
+    };
+    assert_eq!(&*name.borrow_untracked(), "Data");
+    source.borrow_mut().name.push_str(" Soong");
+    assert_eq!(*name_len.borrow_untracked(), 10);
+    assert_eq!(clones.get(), 0);
+}
+
+#[test]
+fn set_if_changed_reports_notification() {
+    init_if_needed();
+    let value = observable(1.5);
+    let num_updates = Rc::new(Cell::new(0));
+    let _derived = derivation_with_ptrs!(value, num_updates; {
+        num_updates.set(num_updates.get() + 1);
+        *value.borrow()
+    });
+    assert!(!value.set_if_changed(1.5));
+    assert_eq!(num_updates.get(), 1);
+    assert!(value.set_if_changed(2.5));
+    assert_eq!(num_updates.get(), 2);
+}
+
+#[test]
+fn untracked_frame_popped_after_panic() {
+    init_if_needed();
+    let value = observable(1);
+    let result = catch_unwind(|| untracked(|| panic!("inside untracked")));
+    assert!(result.is_err());
+    // Without a leftover untracked frame, borrowing outside of a derivation is an error again.
+    let result = catch_unwind(AssertUnwindSafe(|| *value.borrow()));
+    assert!(result.is_err());
+}
+
+#[test]
+fn action_ends_after_panic() {
+    init_if_needed();
+    let value = observable(1);
+    let derived = derivation_with_ptrs!(value; *value.borrow() * 2);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        action(|| {
+            value.set(5);
+            panic!("inside action");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(*derived.borrow_untracked(), 10);
+    value.set(6);
+    assert_eq!(*derived.borrow_untracked(), 12);
+}
+
+#[test]
+#[cfg(any(debug_assertions, feature = "ptr-debug-always"))]
+fn map_evicts_unobserved_placeholders() {
+    init_if_needed();
+    let map = ObservableMap::new();
+    let before = debug::live_node_count();
+    for key in 0..1000 {
+        let reader = derivation_with_ptrs!(map; map.get(&key));
+        assert_eq!(*reader.borrow_untracked(), None);
+    }
+    assert_eq!(map.get(&1000), None);
+    map.insert(-1, 0);
+    // Only the entry for -1 is left.
+    assert_eq!(debug::live_node_count(), before + 1);
+}
+
+#[test]
+fn suspended_resumes_after_panic() {
+    init_if_needed();
+    let value = observable(1);
+    let derived = derivation_with_ptrs!(value; *value.borrow() * 2);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        suspended(|| {
+            value.set(5);
+            panic!("while suspended");
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(*derived.borrow_untracked(), 10);
+    value.set(6);
+    assert_eq!(*derived.borrow_untracked(), 12);
+}
+
+#[test]
+fn chain_within_default_update_depth() {
+    init_if_needed();
+    assert_eq!(max_update_depth(), 1000);
+    let root = observable(0);
+    let end = derivation_chain(&root, 1000);
+    root.set(1);
+    assert_eq!(*end.borrow_untracked(), 1000);
+}
+
+#[test]
+#[should_panic(expected = "More than 1000 derivations were updated inside each other")]
+fn chain_exceeding_default_update_depth() {
+    init_if_needed();
+    let root = observable(0);
+    let _end = derivation_chain(&root, 1001);
+    root.set(1);
+}
+
+#[test]
+fn update_depth_restored_after_panic() {
+    init_if_needed();
+    let _limit = MaxUpdateDepth::set(10);
+    let root = observable(0);
+    let end = derivation_chain(&root, 5);
+    let _fails = derivation_with_ptrs!(end; assert!(*end.borrow() < 5, "end reached 5"));
+    assert!(catch_unwind(AssertUnwindSafe(|| root.set(1))).is_err());
+    // The chain was 6 updates deep when it panicked, this only fits if those were all undone.
+    let other = observable(0);
+    let long = derivation_chain(&other, 10);
+    other.set(1);
+    assert_eq!(*long.borrow_untracked(), 10);
+}